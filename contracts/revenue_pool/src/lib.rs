@@ -1,12 +1,284 @@
 #![no_std]
 
-use soroban_sdk::{contract, contractimpl, token, Address, Env, Symbol};
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, token, Address, BytesN, Env, IntoVal,
+    Symbol, Val, Vec,
+};
 
 /// Revenue settlement contract: receives USDC from vault deducts and distributes to developers.
 ///
-/// Flow: vault deduct → vault transfers USDC to this contract → admin calls distribute(to, amount).
+/// Flow: vault deduct → vault transfers USDC to this contract → admin calls distribute(to, amount),
+/// or the funds are credited to a developer's accrued balance and the developer calls `claim`.
 const ADMIN_KEY: &str = "admin";
 const USDC_KEY: &str = "usdc";
+const PAUSE_KEY: &str = "pause";
+const GUARDIAN_KEY: &str = "guardian";
+const PENDING_ADMIN_KEY: &str = "pending_admin";
+const SCHEMA_VERSION_KEY: &str = "schema_version";
+const TTL_CONFIG_KEY: &str = "ttl_config";
+/// Instance key; pool USDC balance after the last attributed inflow or outflow.
+const CHECKPOINT_KEY: &str = "checkpoint";
+/// Persistent key prefix; `(TRUSTED_VAULT_KEY, vault)` marks a vault allowed to `receive_payment`.
+const TRUSTED_VAULT_KEY: &str = "trusted_vault";
+/// Persistent key prefix; `(RECEIVED_KEY, source)` is the cumulative USDC reported by a source.
+const RECEIVED_KEY: &str = "received";
+/// Instance key; cumulative USDC reported via `receive_payment` across all sources.
+const TOTAL_RECEIVED_KEY: &str = "total_received";
+/// Persistent key prefix; `(DISTRIBUTED_KEY, recipient)` is the cumulative USDC paid out to it.
+const DISTRIBUTED_KEY: &str = "distributed";
+/// Instance key; cumulative USDC paid out by `distribute`, `batch_distribute`, `execute` and `claim`.
+const TOTAL_DISTRIBUTED_KEY: &str = "total_distributed";
+/// Instance key; cumulative USDC returned to vaults via `refund_payment`.
+const TOTAL_REFUNDED_KEY: &str = "total_refunded";
+/// Instance key; sum of all developers' pending credits (part of `TOTAL_ACCRUED_KEY`).
+const TOTAL_PENDING_KEY: &str = "total_pending";
+/// Instance key; `(epoch, amount)`, the part of `TOTAL_PENDING_KEY` credited in that epoch
+/// while it is open (stale once the epoch closes).
+const OPEN_PENDING_KEY: &str = "open_pending";
+/// Persistent key prefix; `(VAULT_PAID_KEY, vault)` is USDC the vault may reclaim via `refund_payment`.
+const VAULT_PAID_KEY: &str = "vault_paid";
+/// Persistent key prefix; `(FROZEN_KEY, vault)` is the part of the vault's refundable USDC under dispute.
+const FROZEN_KEY: &str = "frozen";
+/// Instance key; sum of all frozen amounts, excluded from `distribute`.
+const TOTAL_FROZEN_KEY: &str = "total_frozen";
+/// Persistent key prefix; `(ACCRUED_KEY, developer)` is matured USDC the developer may `claim`.
+const ACCRUED_KEY: &str = "accrued";
+/// Persistent key prefix; `(PENDING_KEY, developer)` is credited USDC still in holdback.
+const PENDING_KEY: &str = "pending";
+/// Instance key; sum of all developers' accrued and pending balances, excluded from `distribute`.
+const TOTAL_ACCRUED_KEY: &str = "total_accrued";
+/// Instance key; ledgers a credit is held back before it can be claimed.
+const HOLDBACK_KEY: &str = "holdback";
+/// Persistent key prefix; `(DEVELOPER_KEY, vault)` is the developer credited with the vault's settlements.
+const DEVELOPER_KEY: &str = "developer";
+/// Persistent key prefix; `(SPLIT_KEY, api_id)` is the API's revenue-share table.
+const SPLIT_KEY: &str = "split";
+/// Instance key; the open payout epoch.
+const CURRENT_EPOCH_KEY: &str = "current_epoch";
+/// Persistent key prefix; `(EPOCH_KEY, id)` is a closed epoch.
+const EPOCH_KEY: &str = "epoch";
+/// Persistent key prefix; `(EPOCH_CREDIT_KEY, id, developer)` is USDC credited to the developer
+/// during the epoch, net of refunds and disputes made while it was open.
+const EPOCH_CREDIT_KEY: &str = "epoch_credit";
+/// Instance key; payout signer set (empty = single-admin payouts).
+const SIGNERS_KEY: &str = "signers";
+/// Instance key; approvals required to execute a proposal.
+const THRESHOLD_KEY: &str = "threshold";
+/// Instance key; id assigned to the next proposal.
+const NEXT_PROPOSAL_KEY: &str = "next_proposal";
+/// Temporary key prefix; `(PROPOSAL_KEY, id)` is an open proposal, kept until it expires.
+const PROPOSAL_KEY: &str = "proposal";
+
+/// Default TTL threshold, in ledgers, below which storage is extended (~30 days at 5s per ledger).
+pub const DEFAULT_TTL_THRESHOLD: u32 = 518_400;
+
+/// Default TTL, in ledgers, that storage is extended to (~60 days at 5s per ledger).
+pub const DEFAULT_TTL_EXTEND_TO: u32 = 1_036_800;
+
+/// Maximum number of payees in a single `batch_distribute` call.
+pub const MAX_DISTRIBUTE_BATCH: u32 = 100;
+
+/// Maximum number of payees in one API's revenue-share table.
+pub const MAX_SPLIT_PAYEES: u32 = 20;
+
+/// Maximum number of payout signers.
+pub const MAX_SIGNERS: u32 = 20;
+
+/// Maximum number of separate pending credits kept per developer; further credits are merged
+/// into existing ones (see `accrue`).
+pub const MAX_PENDING_CREDITS: u32 = 32;
+
+/// Basis points that the shares in a revenue-share table must add up to.
+pub const BPS_DENOMINATOR: u32 = 10_000;
+
+/// Storage layout version written by this release; see `migrate`.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Errors returned by `RevenuePool` entry points.
+///
+/// Codes are stable across releases; clients may match on the numeric value.
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum PoolError {
+    /// `init` was called on a pool that is already initialized.
+    AlreadyInitialized = 1,
+    /// The pool has not been initialized yet.
+    NotInitialized = 2,
+    /// The caller is not permitted to perform this operation.
+    Unauthorized = 3,
+    /// Amount is zero or negative.
+    InvalidAmount = 4,
+    /// The pool holds less USDC than the operation requires.
+    InsufficientUsdc = 5,
+    /// The operation is currently paused.
+    Paused = 6,
+    /// There is no pending admin transfer.
+    NoPendingTransfer = 7,
+    /// The pending transfer's acceptance window has passed.
+    TransferExpired = 8,
+    /// A configuration value is out of range.
+    InvalidConfig = 9,
+    /// `refund_payment` or `freeze_payment` asked for more than the vault's unfrozen
+    /// settlements, or `unfreeze_payment` for more than is frozen.
+    ExceedsRefundable = 10,
+    /// `claim` asked for more than the developer's accrued balance.
+    ExceedsAccrued = 11,
+    /// `batch_distribute` was called with no payees.
+    EmptyBatch = 12,
+    /// `batch_distribute` was called with more than `MAX_DISTRIBUTE_BATCH` payees.
+    BatchTooLarge = 13,
+    /// No revenue-share table exists for the api_id.
+    SplitNotFound = 14,
+    /// No open proposal exists for the id (never created, executed, or expired).
+    ProposalNotFound = 15,
+    /// The proposal passed its `expires_at` ledger.
+    ProposalExpired = 16,
+    /// The signer already approved this proposal.
+    AlreadyApproved = 17,
+    /// The proposal has fewer approvals from current signers than the threshold.
+    ThresholdNotMet = 18,
+    /// `receive_payment` was called by neither the admin nor a registered vault.
+    UnknownSource = 19,
+    /// `receive_payment` reported more USDC than arrived since the last checkpoint.
+    PaymentNotReceived = 20,
+}
+
+/// Admin handover proposed by the current admin and awaiting acceptance.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PendingAdmin {
+    pub new_admin: Address,
+    /// Last ledger sequence at which `accept_admin` succeeds.
+    pub expires_at: u32,
+}
+
+/// Credited revenue held back until ledger `matures_at` (see `set_holdback`).
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PendingCredit {
+    pub amount: i128,
+    pub matures_at: u32,
+    /// Epoch the credit was made in.
+    pub epoch: u64,
+}
+
+/// What a multisig proposal does once executed.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProposalAction {
+    /// Send `amount` of unallocated pool USDC to the address.
+    Payout(Address, i128),
+    /// Replace the signer set and threshold.
+    SetSigners(Vec<Address>, u32),
+    /// `settle` the amount for the api_id.
+    Settle(Symbol, i128),
+    /// `set_split` for the api_id.
+    SetSplit(Symbol, Vec<Split>),
+    /// `remove_split` for the api_id.
+    RemoveSplit(Symbol),
+    /// `set_vault_developer` for the vault.
+    SetVaultDeveloper(Address, Option<Address>),
+    /// `set_holdback` in ledgers.
+    SetHoldback(u32),
+    /// `release_pending` for the developer.
+    ReleasePending(Address),
+    /// `upgrade` to the WASM hash.
+    Upgrade(BytesN<32>),
+    /// `propose_admin` for the new admin, expiring after the given number of ledgers.
+    ProposeAdmin(Address, u32),
+    /// `add_vault` for the vault.
+    AddVault(Address),
+    /// `remove_vault` for the vault.
+    RemoveVault(Address),
+    /// `close_epoch`.
+    CloseEpoch,
+}
+
+/// Multisig proposal awaiting approvals (see `propose_payout`).
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Proposal {
+    pub action: ProposalAction,
+    pub proposer: Address,
+    /// Signers that approved, including the proposer.
+    pub approvals: Vec<Address>,
+    /// Last ledger sequence at which the proposal can be approved or executed.
+    pub expires_at: u32,
+}
+
+/// Statement period for developer payouts. Credits are attributed to the open epoch and can
+/// only be claimed once it is closed (see `close_epoch`).
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Epoch {
+    pub id: u64,
+    /// Ledger sequence at which the epoch opened.
+    pub opened_at: u32,
+    /// Ledger sequence at which the epoch was closed; `None` while open.
+    pub closed_at: Option<u32>,
+    /// USDC credited to developers during the epoch.
+    pub total_credited: i128,
+}
+
+/// Lifetime and current pool totals, as returned by `get_stats`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PoolStats {
+    /// Current USDC balance of the pool.
+    pub balance: i128,
+    /// Cumulative USDC reported via `receive_payment`.
+    pub total_received: i128,
+    /// Cumulative USDC paid out to developers (`distribute`, `batch_distribute`, `execute`,
+    /// `claim`).
+    pub total_distributed: i128,
+    /// Cumulative USDC returned to vaults via `refund_payment`.
+    pub total_refunded: i128,
+    /// USDC frozen for open vault disputes.
+    pub frozen: i128,
+    /// Unclaimed developer credits from the open epoch; none of it is claimable until the
+    /// epoch is closed.
+    pub open_epoch: i128,
+    /// Unclaimed developer credits from closed epochs. Claimable except for credits still
+    /// within their holdback (see `pending_of`, which is tracked per developer).
+    pub closed_epochs: i128,
+    /// Credits still held back, from any epoch. Includes credits whose holdback has passed
+    /// until the developer's balance is next touched.
+    pub pending: i128,
+    /// Matured credits from closed epochs, i.e. what developers may `claim` now. Excludes
+    /// the matured credits counted in `pending` above until they are next touched.
+    pub claimable: i128,
+}
+
+/// One payee's share of an API's revenue, in basis points of each `settle`d amount.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Split {
+    pub payee: Address,
+    pub bps: u32,
+}
+
+/// Rent-extension policy: once an entry's TTL drops below `threshold` ledgers it is
+/// extended to `extend_to` ledgers. Applied to instance storage on every state change and
+/// to persistent entries when they are written.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TtlConfig {
+    pub threshold: u32,
+    pub extend_to: u32,
+}
+
+/// Per-operation pause flags. `true` means the operation is frozen.
+///
+/// Passed to `pause`/`unpause` as the set of operations to change and returned by
+/// `get_pause_state`. Incoming settlements (`receive_payment`) are never paused.
+#[contracttype]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PauseState {
+    pub distribute: bool,
+    /// Developer `claim`s.
+    pub claim: bool,
+}
 
 #[contract]
 pub struct RevenuePool;
@@ -18,10 +290,11 @@ impl RevenuePool {
     /// # Arguments
     /// * `admin` – Address that may call `distribute`. Typically backend or multisig.
     /// * `usdc_token` – Stellar USDC (or wrapped USDC) token contract address.
-    pub fn init(env: Env, admin: Address, usdc_token: Address) {
+    pub fn init(env: Env, admin: Address, usdc_token: Address) -> Result<(), PoolError> {
+        Self::extend_instance_ttl(&env);
         admin.require_auth();
         if env.storage().instance().has(&Symbol::new(&env, ADMIN_KEY)) {
-            panic!("revenue pool already initialized");
+            return Err(PoolError::AlreadyInitialized);
         }
         env.storage()
             .instance()
@@ -29,95 +302,1698 @@ impl RevenuePool {
         env.storage()
             .instance()
             .set(&Symbol::new(&env, USDC_KEY), &usdc_token);
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, SCHEMA_VERSION_KEY), &SCHEMA_VERSION);
+        let epoch = Epoch {
+            id: 0,
+            opened_at: env.ledger().sequence(),
+            closed_at: None,
+            total_credited: 0,
+        };
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, CURRENT_EPOCH_KEY), &epoch);
 
         env.events()
             .publish((Symbol::new(&env, "init"), admin), usdc_token);
+        env.events()
+            .publish((Symbol::new(&env, "epoch_opened"), 0u64), epoch.opened_at);
+        Ok(())
     }
 
     /// Return the current admin address.
-    pub fn get_admin(env: Env) -> Address {
+    pub fn get_admin(env: Env) -> Result<Address, PoolError> {
         env.storage()
             .instance()
             .get(&Symbol::new(&env, ADMIN_KEY))
-            .unwrap_or_else(|| panic!("revenue pool not initialized"))
+            .ok_or(PoolError::NotInitialized)
+    }
+
+    /// Propose `new_admin` as the next admin. Only the current admin may call this.
+    /// The handover completes only when `new_admin` calls `accept_admin` within
+    /// `expires_in_ledgers` ledgers, so a mistyped address never takes control.
+    /// A new proposal replaces any pending one. Once a signer set exists this goes through
+    /// a `ProposeAdmin` proposal instead. Emits an "admin_proposed" event.
+    pub fn propose_admin(
+        env: Env,
+        caller: Address,
+        new_admin: Address,
+        expires_in_ledgers: u32,
+    ) -> Result<PendingAdmin, PoolError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_payout_admin(&env, &caller)?;
+        if expires_in_ledgers == 0 {
+            return Err(PoolError::InvalidConfig);
+        }
+        Ok(Self::apply_propose_admin(
+            &env,
+            caller,
+            new_admin,
+            expires_in_ledgers,
+        ))
+    }
+
+    /// Accept a pending admin handover. Must be signed by the proposed admin before the
+    /// deadline. Emits an "admin_changed" event with the old and new admin.
+    pub fn accept_admin(env: Env) -> Result<Address, PoolError> {
+        Self::extend_instance_ttl(&env);
+        let pending = Self::get_pending_admin(env.clone()).ok_or(PoolError::NoPendingTransfer)?;
+        pending.new_admin.require_auth();
+        if env.ledger().sequence() > pending.expires_at {
+            return Err(PoolError::TransferExpired);
+        }
+
+        let old_admin = Self::get_admin(env.clone())?;
+        let inst = env.storage().instance();
+        inst.set(&Symbol::new(&env, ADMIN_KEY), &pending.new_admin);
+        inst.remove(&Symbol::new(&env, PENDING_ADMIN_KEY));
+
+        env.events().publish(
+            (
+                Symbol::new(&env, "admin_changed"),
+                old_admin,
+                pending.new_admin.clone(),
+            ),
+            (),
+        );
+        Ok(pending.new_admin)
     }
 
-    /// Replace the current admin. Only the existing admin may call this.
-    pub fn set_admin(env: Env, caller: Address, new_admin: Address) {
+    /// Withdraw a pending admin proposal. Only the current admin may call this, and only
+    /// while no signer set exists (signers replace a pending handover with a new
+    /// `ProposeAdmin` proposal). Emits an "admin_cancelled" event with the admin and the
+    /// withdrawn candidate.
+    pub fn cancel_admin_transfer(env: Env, caller: Address) -> Result<(), PoolError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_payout_admin(&env, &caller)?;
+        let pending = Self::get_pending_admin(env.clone()).ok_or(PoolError::NoPendingTransfer)?;
+        env.storage()
+            .instance()
+            .remove(&Symbol::new(&env, PENDING_ADMIN_KEY));
+
+        env.events().publish(
+            (
+                Symbol::new(&env, "admin_cancelled"),
+                caller,
+                pending.new_admin,
+            ),
+            (),
+        );
+        Ok(())
+    }
+
+    /// Return the pending admin handover, if any.
+    pub fn get_pending_admin(env: Env) -> Option<PendingAdmin> {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, PENDING_ADMIN_KEY))
+    }
+
+    /// Set or clear the guardian: an address that may `pause` but not `unpause`. Admin only.
+    pub fn set_guardian(
+        env: Env,
+        caller: Address,
+        guardian: Option<Address>,
+    ) -> Result<(), PoolError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_admin(&env, &caller)?;
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, GUARDIAN_KEY), &guardian);
+        Ok(())
+    }
+
+    /// Return the guardian address, if one is set.
+    pub fn get_guardian(env: Env) -> Option<Address> {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, GUARDIAN_KEY))
+            .unwrap_or(None)
+    }
+
+    /// Freeze every operation flagged in `ops`. Callable by the admin or the guardian.
+    /// Emits a "pause" event with the caller and the requested operation set.
+    pub fn pause(env: Env, caller: Address, ops: PauseState) -> Result<PauseState, PoolError> {
+        Self::extend_instance_ttl(&env);
         caller.require_auth();
-        let current = Self::get_admin(env.clone());
-        if caller != current {
-            panic!("unauthorized: caller is not admin");
+        let admin = Self::get_admin(env.clone())?;
+        if caller != admin && Self::get_guardian(env.clone()) != Some(caller.clone()) {
+            return Err(PoolError::Unauthorized);
         }
+
+        let mut state = Self::get_pause_state(env.clone());
+        state.distribute |= ops.distribute;
+        state.claim |= ops.claim;
         env.storage()
             .instance()
-            .set(&Symbol::new(&env, ADMIN_KEY), &new_admin);
+            .set(&Symbol::new(&env, PAUSE_KEY), &state);
+
+        env.events()
+            .publish((Symbol::new(&env, "pause"), caller), ops);
+        Ok(state)
+    }
+
+    /// Resume every operation flagged in `ops`. Admin only.
+    /// Emits an "unpause" event with the caller and the requested operation set.
+    pub fn unpause(env: Env, caller: Address, ops: PauseState) -> Result<PauseState, PoolError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_admin(&env, &caller)?;
+
+        let mut state = Self::get_pause_state(env.clone());
+        state.distribute &= !ops.distribute;
+        state.claim &= !ops.claim;
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, PAUSE_KEY), &state);
+
+        env.events()
+            .publish((Symbol::new(&env, "unpause"), caller), ops);
+        Ok(state)
     }
 
-    /// Placeholder: record that payment was received (e.g. from vault).
-    /// In practice, USDC is received when the vault (or any address) transfers tokens
-    /// to this contract's address; no separate "receive" call is required.
+    /// Return which operations are currently paused.
+    pub fn get_pause_state(env: Env) -> PauseState {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, PAUSE_KEY))
+            .unwrap_or_default()
+    }
+
+    /// Record a settlement of `amount` USDC that was transferred to this contract.
     ///
-    /// This function can be used to emit an event for indexers when the backend
-    /// wants to log that a payment was credited from the vault.
+    /// The vault calls this after every deduct settlement with `caller` set to the vault's
+    /// own address, so indexers can attribute pool inflows to the source vault. The admin may
+    /// also call it (with `from_vault` false) to log a manual top-up.
     ///
-    /// # Arguments
-    /// * `caller` – Must be admin (or could be extended to allow vault to call).
-    /// * `amount` – Amount received (for event logging).
-    /// * `from_vault` – Optional; true if the source was the vault.
-    pub fn receive_payment(env: Env, caller: Address, amount: i128, from_vault: bool) {
+    /// The pool's USDC balance must have grown by at least `amount` since the last
+    /// checkpoint; the checkpoint then moves to the current balance and `amount` is added to
+    /// the caller's cumulative total (see `get_received`).
+    ///
+    /// When `from_vault` is true, `caller` becomes refundable (see `refund_payment`) for
+    /// `amount`, and the same amount is credited to the developer mapped to the vault (see
+    /// `set_vault_developer`).
+    ///
+    /// A settling vault's registry entry and developer mapping have their TTLs extended, so
+    /// vaults in active use never lapse out of the registry.
+    ///
+    /// # Errors
+    /// * `UnknownSource`      – caller is not the admin or a registered vault (see `add_vault`),
+    ///   or `from_vault` is set by a caller that is not a registered vault.
+    /// * `PaymentNotReceived` – less than `amount` arrived since the last checkpoint.
+    pub fn receive_payment(
+        env: Env,
+        caller: Address,
+        amount: i128,
+        from_vault: bool,
+    ) -> Result<(), PoolError> {
+        Self::extend_instance_ttl(&env);
         caller.require_auth();
-        let _admin = Self::get_admin(env.clone());
+        let trusted = Self::is_trusted_vault(env.clone(), caller.clone());
+        if !trusted && (from_vault || caller != Self::get_admin(env.clone())?) {
+            return Err(PoolError::UnknownSource);
+        }
+        if trusted {
+            Self::extend_persistent_ttl(
+                &env,
+                &(Symbol::new(&env, TRUSTED_VAULT_KEY), caller.clone()),
+            );
+        }
+        if amount <= 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        let usdc = token::Client::new(&env, &Self::get_usdc(&env)?);
+        let current = usdc.balance(&env.current_contract_address());
+        if current - Self::get_checkpoint(&env) < amount {
+            return Err(PoolError::PaymentNotReceived);
+        }
+        Self::set_checkpoint(&env, current);
+
+        let key = (Symbol::new(&env, RECEIVED_KEY), caller.clone());
+        let received = Self::get_received(env.clone(), caller.clone()) + amount;
+        env.storage().persistent().set(&key, &received);
+        Self::extend_persistent_ttl(&env, &key);
+        Self::add_to_total(&env, TOTAL_RECEIVED_KEY, amount);
+        if from_vault {
+            let refundable = Self::get_refundable(env.clone(), caller.clone()) + amount;
+            Self::set_refundable(&env, &caller, refundable);
+            if let Some(developer) = Self::get_vault_developer(env.clone(), caller.clone()) {
+                Self::extend_persistent_ttl(
+                    &env,
+                    &(Symbol::new(&env, DEVELOPER_KEY), caller.clone()),
+                );
+                Self::accrue(&env, &developer, amount);
+            }
+        }
         env.events().publish(
             (Symbol::new(&env, "receive_payment"), caller),
             (amount, from_vault),
         );
+        Ok(())
+    }
+
+    /// Return the cumulative USDC paid out to `recipient`.
+    pub fn get_distributed(env: Env, recipient: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&(Symbol::new(&env, DISTRIBUTED_KEY), recipient))
+            .unwrap_or(0)
+    }
+
+    /// Return lifetime and current pool totals for reconciliation.
+    pub fn get_stats(env: Env) -> Result<PoolStats, PoolError> {
+        let current = Self::get_current_epoch(&env);
+        let open_epoch = current.total_credited;
+        let total_accrued = Self::get_total_accrued(&env);
+        let pending = Self::get_total(&env, TOTAL_PENDING_KEY);
+        let matured_open = open_epoch - Self::get_open_pending(&env, current.id);
+        Ok(PoolStats {
+            balance: Self::balance(env.clone())?,
+            total_received: Self::get_total(&env, TOTAL_RECEIVED_KEY),
+            total_distributed: Self::get_total(&env, TOTAL_DISTRIBUTED_KEY),
+            total_refunded: Self::get_total(&env, TOTAL_REFUNDED_KEY),
+            frozen: Self::get_total_frozen(&env),
+            open_epoch,
+            closed_epochs: total_accrued - open_epoch,
+            pending,
+            claimable: (total_accrued - pending - matured_open).max(0),
+        })
+    }
+
+    /// Return the cumulative USDC `source` has reported via `receive_payment`.
+    pub fn get_received(env: Env, source: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&(Symbol::new(&env, RECEIVED_KEY), source))
+            .unwrap_or(0)
+    }
+
+    /// Register `vault` as a trusted settlement source for `receive_payment`. Admin only
+    /// (an `AddVault` proposal once a signer set exists). Emits a "vault_added" event.
+    pub fn add_vault(env: Env, caller: Address, vault: Address) -> Result<(), PoolError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_payout_admin(&env, &caller)?;
+        Self::apply_add_vault(&env, vault);
+        Ok(())
+    }
+
+    /// Remove `vault` from the trusted settlement sources. Admin only (a `RemoveVault`
+    /// proposal once a signer set exists). Its refundable and frozen balances are kept.
+    /// Emits a "vault_removed" event.
+    pub fn remove_vault(env: Env, caller: Address, vault: Address) -> Result<(), PoolError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_payout_admin(&env, &caller)?;
+        Self::apply_remove_vault(&env, vault);
+        Ok(())
+    }
+
+    /// Return whether `vault` may report settlements via `receive_payment`.
+    pub fn is_trusted_vault(env: Env, vault: Address) -> bool {
+        env.storage()
+            .persistent()
+            .has(&(Symbol::new(&env, TRUSTED_VAULT_KEY), vault))
     }
 
     /// Distribute USDC from this contract to a developer wallet.
     ///
-    /// Only the admin may call. Transfers USDC from this contract to `to`.
+    /// Only the admin may call, only while distribute is not paused, and only while no payout
+    /// signer set is configured (see `set_signers`).
+    /// USDC frozen for open vault disputes (see `freeze_payment`) or credited to developers
+    /// (see `accrued_of`) cannot be distributed.
+    /// Transfers USDC from this contract to `to`.
     ///
     /// # Arguments
     /// * `caller` – Must be the current admin.
     /// * `to` – Developer address to receive USDC.
     /// * `amount` – Amount in token base units (e.g. USDC stroops).
-    pub fn distribute(env: Env, caller: Address, to: Address, amount: i128) {
-        caller.require_auth();
-        let admin = Self::get_admin(env.clone());
-        if caller != admin {
-            panic!("unauthorized: caller is not admin");
+    pub fn distribute(
+        env: Env,
+        caller: Address,
+        to: Address,
+        amount: i128,
+    ) -> Result<(), PoolError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_payout_admin(&env, &caller)?;
+        if Self::get_pause_state(env.clone()).distribute {
+            return Err(PoolError::Paused);
         }
         if amount <= 0 {
-            panic!("amount must be positive");
+            return Err(PoolError::InvalidAmount);
         }
 
-        let usdc_address: Address = env
-            .storage()
-            .instance()
-            .get(&Symbol::new(&env, USDC_KEY))
-            .unwrap_or_else(|| panic!("revenue pool not initialized"));
-        let usdc = token::Client::new(&env, &usdc_address);
+        let usdc = token::Client::new(&env, &Self::get_usdc(&env)?);
 
         let contract_address = env.current_contract_address();
-        if usdc.balance(&contract_address) < amount {
-            panic!("insufficient USDC balance");
+        if Self::unallocated(&env, &usdc) < amount {
+            return Err(PoolError::InsufficientUsdc);
         }
 
         usdc.transfer(&contract_address, &to, &amount);
+        Self::set_checkpoint(&env, usdc.balance(&contract_address));
+        Self::record_payout(&env, &to, amount);
         env.events()
             .publish((Symbol::new(&env, "distribute"), to), amount);
+        Ok(())
+    }
+
+    /// Distribute USDC to many developers in one call, all or nothing.
+    /// Same access, pause and available-funds rules as `distribute`, checked once against the
+    /// total. At most `MAX_DISTRIBUTE_BATCH` payees per call.
+    /// Emits a "distribute" event per payee, then a "batch_distribute" event with the caller,
+    /// payee count and total.
+    ///
+    /// # Errors
+    /// * `EmptyBatch`       – `payouts` is empty.
+    /// * `BatchTooLarge`    – more than `MAX_DISTRIBUTE_BATCH` payees.
+    /// * `InvalidAmount`    – any amount is zero or negative.
+    /// * `InsufficientUsdc` – the total exceeds unfrozen, uncredited pool USDC.
+    pub fn batch_distribute(
+        env: Env,
+        caller: Address,
+        payouts: Vec<(Address, i128)>,
+    ) -> Result<i128, PoolError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_payout_admin(&env, &caller)?;
+        if Self::get_pause_state(env.clone()).distribute {
+            return Err(PoolError::Paused);
+        }
+        if payouts.is_empty() {
+            return Err(PoolError::EmptyBatch);
+        }
+        if payouts.len() > MAX_DISTRIBUTE_BATCH {
+            return Err(PoolError::BatchTooLarge);
+        }
+
+        let mut total = 0i128;
+        for (_, amount) in payouts.iter() {
+            if amount <= 0 {
+                return Err(PoolError::InvalidAmount);
+            }
+            total = total.checked_add(amount).ok_or(PoolError::InvalidAmount)?;
+        }
+        let usdc = token::Client::new(&env, &Self::get_usdc(&env)?);
+        if Self::unallocated(&env, &usdc) < total {
+            return Err(PoolError::InsufficientUsdc);
+        }
+
+        let contract_address = env.current_contract_address();
+        for (to, amount) in payouts.iter() {
+            usdc.transfer(&contract_address, &to, &amount);
+            Self::record_payout(&env, &to, amount);
+            env.events()
+                .publish((Symbol::new(&env, "distribute"), to), amount);
+        }
+        Self::set_checkpoint(&env, usdc.balance(&contract_address));
+        env.events().publish(
+            (Symbol::new(&env, "batch_distribute"), caller),
+            (payouts.len(), total),
+        );
+        Ok(total)
+    }
+
+    /// Return `amount` of previously settled USDC to the vault `caller` (e.g. for a customer
+    /// refund). Must be signed by the vault; limited to what it has settled via
+    /// `receive_payment`, not yet reclaimed, and not frozen. The refund is taken back out of
+    /// the unclaimed balance of the developer mapped to the vault, if any, starting with the
+    /// most recent pending credits.
+    /// Emits a "refund_payment" event with the vault and amount.
+    ///
+    /// # Errors
+    /// * `ExceedsRefundable` – amount exceeds the vault's refundable settlements.
+    /// * `InsufficientUsdc`  – the pool no longer holds enough USDC (already distributed).
+    pub fn refund_payment(env: Env, caller: Address, amount: i128) -> Result<(), PoolError> {
+        Self::extend_instance_ttl(&env);
+        caller.require_auth();
+        if amount <= 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        let refundable = Self::get_refundable(env.clone(), caller.clone());
+        if amount > refundable - Self::get_frozen(env.clone(), caller.clone()) {
+            return Err(PoolError::ExceedsRefundable);
+        }
+
+        if let Some(developer) = Self::get_vault_developer(env.clone(), caller.clone()) {
+            Self::debit(&env, &developer, amount);
+        }
+        let usdc = token::Client::new(&env, &Self::get_usdc(&env)?);
+        let contract_address = env.current_contract_address();
+        if Self::unallocated(&env, &usdc) < amount {
+            return Err(PoolError::InsufficientUsdc);
+        }
+        usdc.transfer(&contract_address, &caller, &amount);
+        Self::set_checkpoint(&env, usdc.balance(&contract_address));
+        Self::set_refundable(&env, &caller, refundable - amount);
+        Self::add_to_total(&env, TOTAL_REFUNDED_KEY, amount);
+
+        env.events()
+            .publish((Symbol::new(&env, "refund_payment"), caller), amount);
+        Ok(())
+    }
+
+    /// Freeze `amount` of the vault `caller`'s refundable USDC while a charge is disputed.
+    /// Frozen USDC cannot be distributed, refunded or claimed until `unfreeze_payment`; for a
+    /// vault mapped to a developer it is moved out of the developer's pending and accrued
+    /// balances.
+    /// Must be signed by the vault. Emits a "freeze_payment" event with the vault and amount.
+    pub fn freeze_payment(env: Env, caller: Address, amount: i128) -> Result<(), PoolError> {
+        Self::extend_instance_ttl(&env);
+        caller.require_auth();
+        if amount <= 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        let frozen = Self::get_frozen(env.clone(), caller.clone());
+        if amount > Self::get_refundable(env.clone(), caller.clone()) - frozen {
+            return Err(PoolError::ExceedsRefundable);
+        }
+        if let Some(developer) = Self::get_vault_developer(env.clone(), caller.clone()) {
+            let owed = Self::accrued_of(env.clone(), developer.clone())
+                + Self::pending_of(env.clone(), developer.clone());
+            if amount > owed {
+                return Err(PoolError::ExceedsRefundable);
+            }
+            Self::debit(&env, &developer, amount);
+        }
+
+        Self::set_frozen(&env, &caller, frozen + amount);
+        env.events()
+            .publish((Symbol::new(&env, "freeze_payment"), caller), amount);
+        Ok(())
+    }
+
+    /// Release `amount` previously frozen by the vault `caller`, crediting it back to the
+    /// mapped developer, if any (subject to a fresh holdback). Must be signed by the vault.
+    /// Emits an "unfreeze_payment" event with the vault and amount.
+    pub fn unfreeze_payment(env: Env, caller: Address, amount: i128) -> Result<(), PoolError> {
+        Self::extend_instance_ttl(&env);
+        caller.require_auth();
+        if amount <= 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        let frozen = Self::get_frozen(env.clone(), caller.clone());
+        if amount > frozen {
+            return Err(PoolError::ExceedsRefundable);
+        }
+
+        Self::set_frozen(&env, &caller, frozen - amount);
+        if let Some(developer) = Self::get_vault_developer(env.clone(), caller.clone()) {
+            Self::accrue(&env, &developer, amount);
+        }
+        env.events()
+            .publish((Symbol::new(&env, "unfreeze_payment"), caller), amount);
+        Ok(())
+    }
+
+    /// Return how much of `vault`'s refundable USDC is frozen for open disputes.
+    pub fn get_frozen(env: Env, vault: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&(Symbol::new(&env, FROZEN_KEY), vault))
+            .unwrap_or(0)
+    }
+
+    /// Return how much USDC `vault` may still reclaim via `refund_payment`.
+    pub fn get_refundable(env: Env, vault: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&(Symbol::new(&env, VAULT_PAID_KEY), vault))
+            .unwrap_or(0)
+    }
+
+    /// Configure the initial payout signer set. Admin only, and only while none is set:
+    /// afterwards the set changes only through an approved `propose_signers` proposal.
+    /// Once set, every admin path that moves or earmarks funds (`distribute`,
+    /// `batch_distribute`, `credit`, `settle`, `set_split`, `remove_split`,
+    /// `set_vault_developer`, `set_holdback`, `release_pending`), as well as `upgrade`,
+    /// `propose_admin` / `cancel_admin_transfer`, `add_vault` / `remove_vault` and
+    /// `close_epoch`, is disabled for the admin and goes through `propose_payout` / `propose`
+    /// / `approve` / `execute` instead.
+    /// Emits a "signers_set" event with the signers and threshold.
+    ///
+    /// # Errors
+    /// * `Unauthorized`  – a signer set already exists.
+    /// * `InvalidConfig` – empty or duplicate signers, more than `MAX_SIGNERS`, or a threshold
+    ///   outside `1..=signers.len()`.
+    pub fn set_signers(
+        env: Env,
+        caller: Address,
+        signers: Vec<Address>,
+        threshold: u32,
+    ) -> Result<(), PoolError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_admin(&env, &caller)?;
+        if !Self::get_signers(env.clone()).is_empty() {
+            return Err(PoolError::Unauthorized);
+        }
+        Self::validate_signers(&signers, threshold)?;
+        Self::store_signers(&env, signers, threshold);
+        Ok(())
+    }
+
+    /// Return the payout signer set (empty when payouts are single-admin).
+    pub fn get_signers(env: Env) -> Vec<Address> {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, SIGNERS_KEY))
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Return the number of approvals a proposal needs (0 when no signer set is configured).
+    pub fn get_threshold(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, THRESHOLD_KEY))
+            .unwrap_or(0)
+    }
+
+    /// Propose paying `amount` of unallocated pool USDC to `to`. Must be signed by a signer,
+    /// whose approval is counted. The proposal can be approved and executed for
+    /// `expires_in_ledgers` ledgers. Emits a "proposal_created" event; returns the id.
+    pub fn propose_payout(
+        env: Env,
+        proposer: Address,
+        to: Address,
+        amount: i128,
+        expires_in_ledgers: u32,
+    ) -> Result<u64, PoolError> {
+        if amount <= 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        Self::create_proposal(
+            &env,
+            proposer,
+            ProposalAction::Payout(to, amount),
+            expires_in_ledgers,
+        )
+    }
+
+    /// Propose any multisig action, e.g. a `settle` or split change once a signer set governs
+    /// the pool. Same rules as `propose_payout`; the action's arguments are validated like
+    /// the corresponding admin call.
+    pub fn propose(
+        env: Env,
+        proposer: Address,
+        action: ProposalAction,
+        expires_in_ledgers: u32,
+    ) -> Result<u64, PoolError> {
+        match &action {
+            ProposalAction::Payout(_, amount) | ProposalAction::Settle(_, amount) => {
+                if *amount <= 0 {
+                    return Err(PoolError::InvalidAmount);
+                }
+            }
+            ProposalAction::SetSigners(signers, threshold) => {
+                Self::validate_signers(signers, *threshold)?;
+            }
+            ProposalAction::SetSplit(_, splits) => Self::validate_splits(splits)?,
+            ProposalAction::ProposeAdmin(_, expires_in_ledgers) => {
+                if *expires_in_ledgers == 0 {
+                    return Err(PoolError::InvalidConfig);
+                }
+            }
+            ProposalAction::RemoveSplit(_)
+            | ProposalAction::SetVaultDeveloper(_, _)
+            | ProposalAction::SetHoldback(_)
+            | ProposalAction::ReleasePending(_)
+            | ProposalAction::Upgrade(_)
+            | ProposalAction::AddVault(_)
+            | ProposalAction::RemoveVault(_)
+            | ProposalAction::CloseEpoch => {}
+        }
+        Self::create_proposal(&env, proposer, action, expires_in_ledgers)
+    }
+
+    /// Propose replacing the signer set and threshold. Same rules as `propose_payout`; the
+    /// new set is validated like `set_signers`.
+    pub fn propose_signers(
+        env: Env,
+        proposer: Address,
+        signers: Vec<Address>,
+        threshold: u32,
+        expires_in_ledgers: u32,
+    ) -> Result<u64, PoolError> {
+        Self::validate_signers(&signers, threshold)?;
+        Self::create_proposal(
+            &env,
+            proposer,
+            ProposalAction::SetSigners(signers, threshold),
+            expires_in_ledgers,
+        )
+    }
+
+    /// Add `signer`'s approval to an open proposal. Must be signed by a current signer.
+    /// Emits a "proposal_approved" event with the id, signer and approval count.
+    pub fn approve(env: Env, signer: Address, proposal_id: u64) -> Result<u32, PoolError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_signer(&env, &signer)?;
+        let key = (Symbol::new(&env, PROPOSAL_KEY), proposal_id);
+        let mut proposal =
+            Self::get_proposal(env.clone(), proposal_id).ok_or(PoolError::ProposalNotFound)?;
+        if env.ledger().sequence() > proposal.expires_at {
+            return Err(PoolError::ProposalExpired);
+        }
+        if proposal.approvals.contains(&signer) {
+            return Err(PoolError::AlreadyApproved);
+        }
+
+        proposal.approvals.push_back(signer.clone());
+        env.storage().temporary().set(&key, &proposal);
+        env.events().publish(
+            (Symbol::new(&env, "proposal_approved"), proposal_id, signer),
+            proposal.approvals.len(),
+        );
+        Ok(proposal.approvals.len())
+    }
+
+    /// Carry out a proposal approved by at least `get_threshold()` current signers.
+    /// Callable by anyone. A payout follows the pause and available-funds rules of
+    /// `distribute` and emits its "distribute" event; a signer change emits "signers_set";
+    /// every other action behaves and emits exactly like its admin call, with the pool's own
+    /// address in place of the admin where that event names the caller.
+    /// Emits a "proposal_executed" event with the id and removes the proposal.
+    ///
+    /// # Errors
+    /// * `ProposalNotFound` – no open proposal for `proposal_id`.
+    /// * `ProposalExpired`  – the proposal passed its expiry.
+    /// * `ThresholdNotMet`  – not enough approvals from current signers.
+    pub fn execute(env: Env, proposal_id: u64) -> Result<(), PoolError> {
+        Self::extend_instance_ttl(&env);
+        let key = (Symbol::new(&env, PROPOSAL_KEY), proposal_id);
+        let proposal =
+            Self::get_proposal(env.clone(), proposal_id).ok_or(PoolError::ProposalNotFound)?;
+        if env.ledger().sequence() > proposal.expires_at {
+            return Err(PoolError::ProposalExpired);
+        }
+        let signers = Self::get_signers(env.clone());
+        let mut approvals = 0u32;
+        for signer in proposal.approvals.iter() {
+            if signers.contains(&signer) {
+                approvals += 1;
+            }
+        }
+        if approvals < Self::get_threshold(env.clone()) {
+            return Err(PoolError::ThresholdNotMet);
+        }
+
+        match proposal.action {
+            ProposalAction::Payout(to, amount) => {
+                if Self::get_pause_state(env.clone()).distribute {
+                    return Err(PoolError::Paused);
+                }
+                let usdc = token::Client::new(&env, &Self::get_usdc(&env)?);
+                if Self::unallocated(&env, &usdc) < amount {
+                    return Err(PoolError::InsufficientUsdc);
+                }
+                let contract_address = env.current_contract_address();
+                usdc.transfer(&contract_address, &to, &amount);
+                Self::set_checkpoint(&env, usdc.balance(&contract_address));
+                Self::record_payout(&env, &to, amount);
+                env.events()
+                    .publish((Symbol::new(&env, "distribute"), to), amount);
+            }
+            ProposalAction::SetSigners(signers, threshold) => {
+                Self::store_signers(&env, signers, threshold);
+            }
+            ProposalAction::Settle(api_id, amount) => Self::apply_settle(&env, api_id, amount)?,
+            ProposalAction::SetSplit(api_id, splits) => {
+                Self::validate_splits(&splits)?;
+                Self::apply_split(&env, api_id, splits);
+            }
+            ProposalAction::RemoveSplit(api_id) => Self::apply_remove_split(&env, api_id)?,
+            ProposalAction::SetVaultDeveloper(vault, developer) => {
+                Self::apply_vault_developer(&env, vault, developer)
+            }
+            ProposalAction::SetHoldback(ledgers) => Self::apply_holdback(&env, ledgers),
+            ProposalAction::ReleasePending(developer) => {
+                Self::apply_release_pending(&env, developer);
+            }
+            ProposalAction::Upgrade(new_wasm_hash) => {
+                Self::apply_upgrade(&env, env.current_contract_address(), new_wasm_hash)
+            }
+            ProposalAction::ProposeAdmin(new_admin, expires_in_ledgers) => {
+                Self::apply_propose_admin(
+                    &env,
+                    env.current_contract_address(),
+                    new_admin,
+                    expires_in_ledgers,
+                );
+            }
+            ProposalAction::AddVault(vault) => Self::apply_add_vault(&env, vault),
+            ProposalAction::RemoveVault(vault) => Self::apply_remove_vault(&env, vault),
+            ProposalAction::CloseEpoch => {
+                Self::apply_close_epoch(&env);
+            }
+        }
+
+        env.storage().temporary().remove(&key);
+        env.events()
+            .publish((Symbol::new(&env, "proposal_executed"), proposal_id), ());
+        Ok(())
+    }
+
+    /// Return an open proposal, if it exists and has not been executed or archived.
+    pub fn get_proposal(env: Env, proposal_id: u64) -> Option<Proposal> {
+        env.storage()
+            .temporary()
+            .get(&(Symbol::new(&env, PROPOSAL_KEY), proposal_id))
+    }
+
+    /// Map `vault` to the developer credited with its future settlements, or clear the
+    /// mapping with `None`. Admin only; with a signer set, use a `SetVaultDeveloper` proposal.
+    /// Emits a "vault_developer_set" event with the vault and the old and new developer.
+    pub fn set_vault_developer(
+        env: Env,
+        caller: Address,
+        vault: Address,
+        developer: Option<Address>,
+    ) -> Result<(), PoolError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_payout_admin(&env, &caller)?;
+        Self::apply_vault_developer(&env, vault, developer);
+        Ok(())
+    }
+
+    /// Return the developer credited with `vault`'s settlements, if any.
+    pub fn get_vault_developer(env: Env, vault: Address) -> Option<Address> {
+        env.storage()
+            .persistent()
+            .get(&(Symbol::new(&env, DEVELOPER_KEY), vault))
+    }
+
+    /// Credit `amount` of unallocated pool USDC to `developer`. Admin only, and only while no
+    /// payout signer set is configured.
+    /// The credit is pending until the holdback passes (see `set_holdback`).
+    /// Emits a "credit" event with the developer and amount. Returns the developer's matured
+    /// balance, including the open epoch (see `accrued_of`; `claimable_of` is what can be
+    /// claimed now).
+    ///
+    /// # Errors
+    /// * `InsufficientUsdc` – the pool holds less unallocated USDC than `amount`.
+    pub fn credit(
+        env: Env,
+        caller: Address,
+        developer: Address,
+        amount: i128,
+    ) -> Result<i128, PoolError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_payout_admin(&env, &caller)?;
+        if amount <= 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        let usdc = token::Client::new(&env, &Self::get_usdc(&env)?);
+        if Self::unallocated(&env, &usdc) < amount {
+            return Err(PoolError::InsufficientUsdc);
+        }
+
+        Self::accrue(&env, &developer, amount);
+        env.events()
+            .publish((Symbol::new(&env, "credit"), developer.clone()), amount);
+        Ok(Self::accrued_of(env, developer))
+    }
+
+    /// Pay `amount` of the developer's accrued balance out to the developer. Must be signed by
+    /// `developer`, and fails with `Paused` while claims are paused. Only credits from closed
+    /// epochs can be claimed (see `claimable_of`).
+    /// Emits a "claim" event with the developer and amount. Returns the remaining balance.
+    ///
+    /// # Errors
+    /// * `ExceedsAccrued` – amount is more than `claimable_of(developer)`.
+    pub fn claim(env: Env, developer: Address, amount: i128) -> Result<i128, PoolError> {
+        Self::extend_instance_ttl(&env);
+        developer.require_auth();
+        if Self::get_pause_state(env.clone()).claim {
+            return Err(PoolError::Paused);
+        }
+        if amount <= 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        Self::mature(&env, &developer);
+        let accrued = Self::stored_accrued(&env, &developer);
+        if amount > Self::claimable_of(env.clone(), developer.clone()) {
+            return Err(PoolError::ExceedsAccrued);
+        }
+
+        let usdc = token::Client::new(&env, &Self::get_usdc(&env)?);
+        let contract_address = env.current_contract_address();
+        usdc.transfer(&contract_address, &developer, &amount);
+        Self::set_checkpoint(&env, usdc.balance(&contract_address));
+        Self::set_accrued(&env, &developer, accrued - amount);
+        Self::record_payout(&env, &developer, amount);
+
+        env.events()
+            .publish((Symbol::new(&env, "claim"), developer), amount);
+        Ok(accrued - amount)
+    }
+
+    /// Set the revenue-share table for `api_id`, replacing any existing one. Admin only; with
+    /// a signer set, use a `SetSplit` proposal.
+    /// Fails with `InvalidConfig` unless there are 1 to `MAX_SPLIT_PAYEES` payees, each with a
+    /// non-zero share, and the shares sum to `BPS_DENOMINATOR`.
+    /// Emits a "split_set" event with the api_id and the new table.
+    pub fn set_split(
+        env: Env,
+        caller: Address,
+        api_id: Symbol,
+        splits: Vec<Split>,
+    ) -> Result<(), PoolError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_payout_admin(&env, &caller)?;
+        Self::validate_splits(&splits)?;
+        Self::apply_split(&env, api_id, splits);
+        Ok(())
+    }
+
+    /// Delete the revenue-share table for `api_id`. Admin only; with a signer set, use a
+    /// `RemoveSplit` proposal.
+    /// Emits a "split_removed" event with the api_id.
+    pub fn remove_split(env: Env, caller: Address, api_id: Symbol) -> Result<(), PoolError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_payout_admin(&env, &caller)?;
+        Self::apply_remove_split(&env, api_id)
+    }
+
+    /// Return the revenue-share table for `api_id`, if any.
+    pub fn get_split(env: Env, api_id: Symbol) -> Option<Vec<Split>> {
+        env.storage()
+            .persistent()
+            .get(&(Symbol::new(&env, SPLIT_KEY), api_id))
+    }
+
+    /// Credit `amount` of unallocated pool USDC earned by `api_id` to its payees' accrued
+    /// balances according to the API's revenue-share table. Admin only; with a signer set, use
+    /// a `Settle` proposal.
+    /// Each share is rounded down; the rounding remainder goes to the first payee.
+    /// Emits a "credit" event per payee, then a "settle" event with the api_id and amount.
+    ///
+    /// # Errors
+    /// * `SplitNotFound`    – no table is set for `api_id`.
+    /// * `InsufficientUsdc` – the pool holds less unallocated USDC than `amount`.
+    pub fn settle(
+        env: Env,
+        caller: Address,
+        api_id: Symbol,
+        amount: i128,
+    ) -> Result<(), PoolError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_payout_admin(&env, &caller)?;
+        if amount <= 0 {
+            return Err(PoolError::InvalidAmount);
+        }
+        Self::apply_settle(&env, api_id, amount)
+    }
+
+    /// Return the USDC credited to `developer` whose holdback has passed, including credits
+    /// from the still-open epoch. See `claimable_of` for what may be claimed now.
+    pub fn accrued_of(env: Env, developer: Address) -> i128 {
+        let now = env.ledger().sequence();
+        let mut available = Self::stored_accrued(&env, &developer);
+        for credit in Self::get_pending(&env, &developer).iter() {
+            if credit.matures_at <= now {
+                available += credit.amount;
+            }
+        }
+        available
+    }
+
+    /// Return the USDC `developer` can `claim` now: matured credits, less the matured part of
+    /// what was credited in the still-open epoch.
+    pub fn claimable_of(env: Env, developer: Address) -> i128 {
+        let current = Self::get_current_epoch(&env);
+        let open_credit = Self::get_epoch_credit(env.clone(), current.id, developer.clone());
+        let open_pending = Self::open_pending(&env, &developer, current.id);
+        let accrued = Self::accrued_of(env, developer);
+        (accrued - (open_credit - open_pending).max(0)).max(0)
+    }
+
+    /// Close the open epoch, freezing its per-developer credit totals and making them
+    /// claimable, and open the next one. Admin only (a `CloseEpoch` proposal once a signer set
+    /// exists).
+    /// Emits "epoch_closed" with the id and total credited, then "epoch_opened" with the new
+    /// id and its opening ledger. Returns the closed epoch.
+    pub fn close_epoch(env: Env, caller: Address) -> Result<Epoch, PoolError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_payout_admin(&env, &caller)?;
+        Ok(Self::apply_close_epoch(&env))
+    }
+
+    /// Return the epoch with `id`, closed or currently open.
+    pub fn get_epoch(env: Env, id: u64) -> Option<Epoch> {
+        let current = Self::get_current_epoch(&env);
+        if id == current.id {
+            return Some(current);
+        }
+        env.storage()
+            .persistent()
+            .get(&(Symbol::new(&env, EPOCH_KEY), id))
+    }
+
+    /// Return the id of the open epoch (see `get_epoch` for its details).
+    pub fn get_current_epoch_id(env: Env) -> u64 {
+        Self::get_current_epoch(&env).id
+    }
+
+    /// Return the USDC credited to `developer` during epoch `id`.
+    pub fn get_epoch_credit(env: Env, id: u64, developer: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&(Symbol::new(&env, EPOCH_CREDIT_KEY), id, developer))
+            .unwrap_or(0)
+    }
+
+    /// Return the USDC credited to `developer` that is still held back.
+    pub fn pending_of(env: Env, developer: Address) -> i128 {
+        let now = env.ledger().sequence();
+        let mut pending = 0;
+        for credit in Self::get_pending(&env, &developer).iter() {
+            if credit.matures_at > now {
+                pending += credit.amount;
+            }
+        }
+        pending
+    }
+
+    /// Return the holdback, in ledgers, applied to new credits (0 = claimable immediately).
+    pub fn get_holdback(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, HOLDBACK_KEY))
+            .unwrap_or(0)
+    }
+
+    /// Set the holdback, in ledgers, applied to credits made from now on. Admin only; with a
+    /// signer set, use a `SetHoldback` proposal. Credits already pending keep their maturity.
+    pub fn set_holdback(env: Env, caller: Address, ledgers: u32) -> Result<(), PoolError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_payout_admin(&env, &caller)?;
+        Self::apply_holdback(&env, ledgers);
+        Ok(())
+    }
+
+    /// Make all of `developer`'s pending credits claimable now. Admin only; with a signer set,
+    /// use a `ReleasePending` proposal.
+    /// Emits a "pending_released" event with the developer and the amount released.
+    pub fn release_pending(
+        env: Env,
+        caller: Address,
+        developer: Address,
+    ) -> Result<i128, PoolError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_payout_admin(&env, &caller)?;
+        Ok(Self::apply_release_pending(&env, developer))
+    }
+
+    /// Replace this pool's code with the already-uploaded WASM `new_wasm_hash`, keeping the
+    /// contract address and storage. Admin only. Call `migrate` afterwards when the new
+    /// release bumps `SCHEMA_VERSION`. Once a signer set exists, upgrades go through an
+    /// `Upgrade` proposal instead. Emits an "upgraded" event with the new WASM hash.
+    pub fn upgrade(env: Env, caller: Address, new_wasm_hash: BytesN<32>) -> Result<(), PoolError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_payout_admin(&env, &caller)?;
+        Self::apply_upgrade(&env, caller, new_wasm_hash);
+        Ok(())
+    }
+
+    /// Return the storage layout version of this instance (1 for pools predating versioning).
+    pub fn get_schema_version(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, SCHEMA_VERSION_KEY))
+            .unwrap_or(1)
+    }
+
+    /// Convert storage written by an older release to the current `SCHEMA_VERSION` layout.
+    /// Admin only; a no-op when the instance is already current. Returns the resulting version.
+    /// Emits a "migrated" event with the old and new versions.
+    ///
+    /// Steps:
+    /// * 1 → 2: the `receive_payment` checkpoint starts at the current USDC balance, so funds
+    ///   already in the pool cannot be reported again as a new payment.
+    pub fn migrate(env: Env, caller: Address) -> Result<u32, PoolError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_admin(&env, &caller)?;
+        let from = Self::get_schema_version(env.clone());
+        if from >= SCHEMA_VERSION {
+            return Ok(from);
+        }
+
+        if from < 2 {
+            let usdc = token::Client::new(&env, &Self::get_usdc(&env)?);
+            Self::set_checkpoint(&env, usdc.balance(&env.current_contract_address()));
+        }
+
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, SCHEMA_VERSION_KEY), &SCHEMA_VERSION);
+        env.events().publish(
+            (Symbol::new(&env, "migrated"), caller),
+            (from, SCHEMA_VERSION),
+        );
+        Ok(SCHEMA_VERSION)
+    }
+
+    /// Return the rent-extension policy (defaults to `DEFAULT_TTL_THRESHOLD`/`DEFAULT_TTL_EXTEND_TO`).
+    pub fn get_ttl_config(env: Env) -> TtlConfig {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, TTL_CONFIG_KEY))
+            .unwrap_or(TtlConfig {
+                threshold: DEFAULT_TTL_THRESHOLD,
+                extend_to: DEFAULT_TTL_EXTEND_TO,
+            })
+    }
+
+    /// Change the rent-extension policy. Admin only.
+    /// Fails with `InvalidConfig` unless `0 < threshold < extend_to <= ` the network's max TTL.
+    pub fn set_ttl_config(env: Env, caller: Address, config: TtlConfig) -> Result<(), PoolError> {
+        Self::require_admin(&env, &caller)?;
+        if config.threshold == 0
+            || config.threshold >= config.extend_to
+            || config.extend_to > env.storage().max_ttl()
+        {
+            return Err(PoolError::InvalidConfig);
+        }
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, TTL_CONFIG_KEY), &config);
+        Self::extend_instance_ttl(&env);
+        Ok(())
+    }
+
+    /// Extend the pool's instance TTL per `get_ttl_config`. Callable by anyone willing to pay
+    /// the rent.
+    pub fn extend_ttl(env: Env) {
+        Self::extend_instance_ttl(&env);
     }
 
     /// Return this contract's USDC balance (for testing and dashboards).
-    pub fn balance(env: Env) -> i128 {
-        let usdc_address: Address = env
+    pub fn balance(env: Env) -> Result<i128, PoolError> {
+        let usdc = token::Client::new(&env, &Self::get_usdc(&env)?);
+        Ok(usdc.balance(&env.current_contract_address()))
+    }
+}
+
+impl RevenuePool {
+    /// Require `caller`'s signature and that it is the current admin.
+    fn require_admin(env: &Env, caller: &Address) -> Result<(), PoolError> {
+        caller.require_auth();
+        if *caller != Self::get_admin(env.clone())? {
+            return Err(PoolError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    /// Like `require_admin`, but also fails once payouts are governed by a signer set.
+    fn require_payout_admin(env: &Env, caller: &Address) -> Result<(), PoolError> {
+        Self::require_admin(env, caller)?;
+        if !Self::get_signers(env.clone()).is_empty() {
+            return Err(PoolError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    /// Require `signer`'s signature and that it is in the current signer set.
+    fn require_signer(env: &Env, signer: &Address) -> Result<(), PoolError> {
+        signer.require_auth();
+        if !Self::get_signers(env.clone()).contains(signer) {
+            return Err(PoolError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    fn validate_signers(signers: &Vec<Address>, threshold: u32) -> Result<(), PoolError> {
+        if signers.is_empty()
+            || signers.len() > MAX_SIGNERS
+            || threshold == 0
+            || threshold > signers.len()
+        {
+            return Err(PoolError::InvalidConfig);
+        }
+        for (i, signer) in signers.iter().enumerate() {
+            if signers.first_index_of(&signer) != Some(i as u32) {
+                return Err(PoolError::InvalidConfig);
+            }
+        }
+        Ok(())
+    }
+
+    fn store_signers(env: &Env, signers: Vec<Address>, threshold: u32) {
+        let inst = env.storage().instance();
+        inst.set(&Symbol::new(env, SIGNERS_KEY), &signers);
+        inst.set(&Symbol::new(env, THRESHOLD_KEY), &threshold);
+        env.events()
+            .publish((Symbol::new(env, "signers_set"),), (signers, threshold));
+    }
+
+    /// Store a new proposal approved by its proposer and emit "proposal_created".
+    fn create_proposal(
+        env: &Env,
+        proposer: Address,
+        action: ProposalAction,
+        expires_in_ledgers: u32,
+    ) -> Result<u64, PoolError> {
+        Self::extend_instance_ttl(env);
+        Self::require_signer(env, &proposer)?;
+        if expires_in_ledgers == 0 || expires_in_ledgers > env.storage().max_ttl() {
+            return Err(PoolError::InvalidConfig);
+        }
+        let inst = env.storage().instance();
+        let id: u64 = inst.get(&Symbol::new(env, NEXT_PROPOSAL_KEY)).unwrap_or(0);
+        inst.set(&Symbol::new(env, NEXT_PROPOSAL_KEY), &(id + 1));
+
+        let proposal = Proposal {
+            action: action.clone(),
+            proposer: proposer.clone(),
+            approvals: Vec::from_array(env, [proposer.clone()]),
+            expires_at: env.ledger().sequence().saturating_add(expires_in_ledgers),
+        };
+        let key = (Symbol::new(env, PROPOSAL_KEY), id);
+        let temp = env.storage().temporary();
+        temp.set(&key, &proposal);
+        temp.extend_ttl(&key, expires_in_ledgers, expires_in_ledgers);
+
+        env.events().publish(
+            (Symbol::new(env, "proposal_created"), id, proposer),
+            (action, proposal.expires_at),
+        );
+        Ok(id)
+    }
+
+    /// Check a revenue-share table: 1 to `MAX_SPLIT_PAYEES` payees, each with a non-zero
+    /// share, summing to `BPS_DENOMINATOR`.
+    fn validate_splits(splits: &Vec<Split>) -> Result<(), PoolError> {
+        if splits.is_empty() || splits.len() > MAX_SPLIT_PAYEES {
+            return Err(PoolError::InvalidConfig);
+        }
+        let mut total_bps = 0u32;
+        for split in splits.iter() {
+            if split.bps == 0 {
+                return Err(PoolError::InvalidConfig);
+            }
+            total_bps = total_bps.saturating_add(split.bps);
+        }
+        if total_bps != BPS_DENOMINATOR {
+            return Err(PoolError::InvalidConfig);
+        }
+        Ok(())
+    }
+
+    /// Store a validated revenue-share table and emit "split_set".
+    fn apply_split(env: &Env, api_id: Symbol, splits: Vec<Split>) {
+        let key = (Symbol::new(env, SPLIT_KEY), api_id.clone());
+        env.storage().persistent().set(&key, &splits);
+        Self::extend_persistent_ttl(env, &key);
+        env.events()
+            .publish((Symbol::new(env, "split_set"), api_id), splits);
+    }
+
+    /// Delete a revenue-share table and emit "split_removed".
+    fn apply_remove_split(env: &Env, api_id: Symbol) -> Result<(), PoolError> {
+        let key = (Symbol::new(env, SPLIT_KEY), api_id.clone());
+        if !env.storage().persistent().has(&key) {
+            return Err(PoolError::SplitNotFound);
+        }
+        env.storage().persistent().remove(&key);
+        env.events()
+            .publish((Symbol::new(env, "split_removed"), api_id), ());
+        Ok(())
+    }
+
+    /// Credit `amount` of unallocated USDC to `api_id`'s payees (see `settle`).
+    fn apply_settle(env: &Env, api_id: Symbol, amount: i128) -> Result<(), PoolError> {
+        let key = (Symbol::new(env, SPLIT_KEY), api_id.clone());
+        let splits: Vec<Split> = env
             .storage()
+            .persistent()
+            .get(&key)
+            .ok_or(PoolError::SplitNotFound)?;
+        Self::extend_persistent_ttl(env, &key);
+        let usdc = token::Client::new(env, &Self::get_usdc(env)?);
+        if Self::unallocated(env, &usdc) < amount {
+            return Err(PoolError::InsufficientUsdc);
+        }
+
+        let mut remainder = amount;
+        for split in splits.iter() {
+            remainder -= amount * split.bps as i128 / BPS_DENOMINATOR as i128;
+        }
+        for (i, split) in splits.iter().enumerate() {
+            let mut share = amount * split.bps as i128 / BPS_DENOMINATOR as i128;
+            if i == 0 {
+                share += remainder;
+            }
+            if share == 0 {
+                continue;
+            }
+            Self::accrue(env, &split.payee, share);
+            env.events()
+                .publish((Symbol::new(env, "credit"), split.payee), share);
+        }
+
+        env.events()
+            .publish((Symbol::new(env, "settle"), api_id), amount);
+        Ok(())
+    }
+
+    /// Map or unmap `vault`'s developer and emit "vault_developer_set".
+    fn apply_vault_developer(env: &Env, vault: Address, developer: Option<Address>) {
+        let old = Self::get_vault_developer(env.clone(), vault.clone());
+        let key = (Symbol::new(env, DEVELOPER_KEY), vault.clone());
+        match &developer {
+            Some(developer) => {
+                env.storage().persistent().set(&key, developer);
+                Self::extend_persistent_ttl(env, &key);
+            }
+            None => env.storage().persistent().remove(&key),
+        }
+        env.events().publish(
+            (Symbol::new(env, "vault_developer_set"),),
+            (vault, old, developer),
+        );
+    }
+
+    fn apply_holdback(env: &Env, ledgers: u32) {
+        env.storage()
             .instance()
-            .get(&Symbol::new(&env, USDC_KEY))
-            .unwrap_or_else(|| panic!("revenue pool not initialized"));
-        let usdc = token::Client::new(&env, &usdc_address);
+            .set(&Symbol::new(env, HOLDBACK_KEY), &ledgers);
+    }
+
+    fn apply_upgrade(env: &Env, by: Address, new_wasm_hash: BytesN<32>) {
+        env.deployer()
+            .update_current_contract_wasm(new_wasm_hash.clone());
+        env.events()
+            .publish((Symbol::new(env, "upgraded"), by), new_wasm_hash);
+    }
+
+    fn apply_propose_admin(
+        env: &Env,
+        by: Address,
+        new_admin: Address,
+        expires_in_ledgers: u32,
+    ) -> PendingAdmin {
+        let pending = PendingAdmin {
+            new_admin: new_admin.clone(),
+            expires_at: env.ledger().sequence().saturating_add(expires_in_ledgers),
+        };
+        env.storage()
+            .instance()
+            .set(&Symbol::new(env, PENDING_ADMIN_KEY), &pending);
+        env.events().publish(
+            (Symbol::new(env, "admin_proposed"), by, new_admin),
+            pending.expires_at,
+        );
+        pending
+    }
+
+    fn apply_add_vault(env: &Env, vault: Address) {
+        let key = (Symbol::new(env, TRUSTED_VAULT_KEY), vault.clone());
+        env.storage().persistent().set(&key, &true);
+        Self::extend_persistent_ttl(env, &key);
+        env.events()
+            .publish((Symbol::new(env, "vault_added"), vault), ());
+    }
+
+    fn apply_remove_vault(env: &Env, vault: Address) {
+        env.storage()
+            .persistent()
+            .remove(&(Symbol::new(env, TRUSTED_VAULT_KEY), vault.clone()));
+        env.events()
+            .publish((Symbol::new(env, "vault_removed"), vault), ());
+    }
+
+    /// Freeze the open epoch, open the next one and emit "epoch_closed" / "epoch_opened".
+    fn apply_close_epoch(env: &Env) -> Epoch {
+        let mut closed = Self::get_current_epoch(env);
+        let now = env.ledger().sequence();
+        closed.closed_at = Some(now);
+        let key = (Symbol::new(env, EPOCH_KEY), closed.id);
+        env.storage().persistent().set(&key, &closed);
+        Self::extend_persistent_ttl(env, &key);
+
+        let next = Epoch {
+            id: closed.id + 1,
+            opened_at: now,
+            closed_at: None,
+            total_credited: 0,
+        };
+        env.storage()
+            .instance()
+            .set(&Symbol::new(env, CURRENT_EPOCH_KEY), &next);
+
+        env.events().publish(
+            (Symbol::new(env, "epoch_closed"), closed.id),
+            closed.total_credited,
+        );
+        env.events()
+            .publish((Symbol::new(env, "epoch_opened"), next.id), now);
+        closed
+    }
+
+    /// Move all of `developer`'s pending credits to the claimable balance, emit
+    /// "pending_released", and return the amount released.
+    fn apply_release_pending(env: &Env, developer: Address) -> i128 {
+        let pending = Self::get_pending(env, &developer);
+        let mut released = 0;
+        for credit in pending.iter() {
+            released += credit.amount;
+        }
+        Self::set_pending(env, &developer, Vec::new(env), -released);
+        let accrued = Self::stored_accrued(env, &developer) + released;
+        Self::set_accrued(env, &developer, accrued);
+
+        env.events()
+            .publish((Symbol::new(env, "pending_released"), developer), released);
+        released
+    }
+
+    /// Extend the instance (and contract code) TTL per the configured policy.
+    fn extend_instance_ttl(env: &Env) {
+        let config = Self::get_ttl_config(env.clone());
+        env.storage()
+            .instance()
+            .extend_ttl(config.threshold, config.extend_to);
+    }
+
+    /// Pool USDC balance recorded at the last attributed inflow or outflow.
+    fn get_checkpoint(env: &Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(env, CHECKPOINT_KEY))
+            .unwrap_or(0)
+    }
+
+    fn set_checkpoint(env: &Env, balance: i128) {
+        env.storage()
+            .instance()
+            .set(&Symbol::new(env, CHECKPOINT_KEY), &balance);
+    }
+
+    /// Store `vault`'s refundable amount and extend the entry's TTL.
+    fn set_refundable(env: &Env, vault: &Address, amount: i128) {
+        let key = (Symbol::new(env, VAULT_PAID_KEY), vault.clone());
+        env.storage().persistent().set(&key, &amount);
+        Self::extend_persistent_ttl(env, &key);
+    }
+
+    /// Store `vault`'s frozen amount and keep the pool-wide total in step.
+    fn set_frozen(env: &Env, vault: &Address, amount: i128) {
+        let key = (Symbol::new(env, FROZEN_KEY), vault.clone());
+        let previous = Self::get_frozen(env.clone(), vault.clone());
+        let total = Self::get_total_frozen(env) - previous + amount;
+        env.storage()
+            .instance()
+            .set(&Symbol::new(env, TOTAL_FROZEN_KEY), &total);
+        if amount == 0 {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &amount);
+            Self::extend_persistent_ttl(env, &key);
+        }
+    }
+
+    /// `developer`'s matured balance as last written (excludes pending credits that have
+    /// matured since; see `mature`).
+    fn stored_accrued(env: &Env, developer: &Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&(Symbol::new(env, ACCRUED_KEY), developer.clone()))
+            .unwrap_or(0)
+    }
+
+    /// Store `developer`'s matured balance and keep the pool-wide total in step.
+    fn set_accrued(env: &Env, developer: &Address, amount: i128) {
+        let key = (Symbol::new(env, ACCRUED_KEY), developer.clone());
+        let previous = Self::stored_accrued(env, developer);
+        let total = Self::get_total_accrued(env) - previous + amount;
+        env.storage()
+            .instance()
+            .set(&Symbol::new(env, TOTAL_ACCRUED_KEY), &total);
+        if amount == 0 {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &amount);
+            Self::extend_persistent_ttl(env, &key);
+        }
+    }
+
+    /// `developer`'s pending credits, oldest first.
+    fn get_pending(env: &Env, developer: &Address) -> Vec<PendingCredit> {
+        env.storage()
+            .persistent()
+            .get(&(Symbol::new(env, PENDING_KEY), developer.clone()))
+            .unwrap_or(Vec::new(env))
+    }
+
+    /// Store `developer`'s pending credits, whose sum changed by `delta`, and keep the
+    /// pool-wide totals in step.
+    fn set_pending(env: &Env, developer: &Address, pending: Vec<PendingCredit>, delta: i128) {
+        let key = (Symbol::new(env, PENDING_KEY), developer.clone());
+        Self::add_to_total(env, TOTAL_ACCRUED_KEY, delta);
+        Self::add_to_total(env, TOTAL_PENDING_KEY, delta);
+        let epoch = Self::get_current_epoch(env).id;
+        let open_delta = Self::sum_epoch(&pending, epoch)
+            - Self::sum_epoch(&Self::get_pending(env, developer), epoch);
+        if open_delta != 0 {
+            let open = Self::get_open_pending(env, epoch) + open_delta;
+            env.storage()
+                .instance()
+                .set(&Symbol::new(env, OPEN_PENDING_KEY), &(epoch, open));
+        }
+        if pending.is_empty() {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &pending);
+            Self::extend_persistent_ttl(env, &key);
+        }
+    }
+
+    /// Credit `amount` to `developer` in the open epoch, pending for the holdback if one is set.
+    /// A credit maturing on the same ledger as the latest pending one from the same epoch is
+    /// merged into it. Once `MAX_PENDING_CREDITS` are pending, it is merged into that latest
+    /// one at the later maturity, or, if the latest is from a closed epoch, the two oldest
+    /// entries (all from closed epochs, since epochs only grow along the list) are folded
+    /// into one at the later maturity to make room.
+    fn accrue(env: &Env, developer: &Address, amount: i128) {
+        Self::add_epoch_credit(env, developer, amount);
+        let holdback = Self::get_holdback(env.clone());
+        if holdback == 0 {
+            let accrued = Self::stored_accrued(env, developer) + amount;
+            Self::set_accrued(env, developer, accrued);
+            return;
+        }
+        Self::mature(env, developer);
+        let mut pending = Self::get_pending(env, developer);
+        let matures_at = env.ledger().sequence().saturating_add(holdback);
+        let epoch = Self::get_current_epoch(env).id;
+        let merge = match pending.last() {
+            Some(last) => {
+                last.epoch == epoch
+                    && (last.matures_at == matures_at || pending.len() >= MAX_PENDING_CREDITS)
+            }
+            None => false,
+        };
+        if merge {
+            let mut last = pending.pop_back().unwrap();
+            last.amount += amount;
+            last.matures_at = last.matures_at.max(matures_at);
+            pending.push_back(last);
+        } else {
+            if pending.len() >= MAX_PENDING_CREDITS {
+                let oldest = pending.pop_front().unwrap();
+                let mut next = pending.pop_front().unwrap();
+                next.amount += oldest.amount;
+                next.matures_at = next.matures_at.max(oldest.matures_at);
+                pending.push_front(next);
+            }
+            pending.push_back(PendingCredit {
+                amount,
+                matures_at,
+                epoch,
+            });
+        }
+        Self::set_pending(env, developer, pending, amount);
+    }
+
+    /// Move `developer`'s matured pending credits into the claimable balance.
+    fn mature(env: &Env, developer: &Address) {
+        let now = env.ledger().sequence();
+        let mut matured = 0;
+        let mut pending = Vec::new(env);
+        for credit in Self::get_pending(env, developer).iter() {
+            if credit.matures_at <= now {
+                matured += credit.amount;
+            } else {
+                pending.push_back(credit);
+            }
+        }
+        if matured > 0 {
+            Self::set_pending(env, developer, pending, -matured);
+            let accrued = Self::stored_accrued(env, developer) + matured;
+            Self::set_accrued(env, developer, accrued);
+        }
+    }
+
+    /// Take up to `amount` back from `developer`, newest pending credits first, then the
+    /// matured balance (open-epoch credits before older ones).
+    fn debit(env: &Env, developer: &Address, amount: i128) {
+        Self::mature(env, developer);
+        let current = Self::get_current_epoch(env).id;
+        let open_credit = Self::get_epoch_credit(env.clone(), current, developer.clone());
+        let matured_open = (open_credit - Self::open_pending(env, developer, current)).max(0);
+        let mut remaining = amount;
+        let mut taken_open = 0;
+        let mut pending = Self::get_pending(env, developer);
+        while remaining > 0 {
+            let Some(mut credit) = pending.pop_back() else {
+                break;
+            };
+            let taken = credit.amount.min(remaining);
+            remaining -= taken;
+            credit.amount -= taken;
+            if credit.epoch == current {
+                taken_open += taken;
+            }
+            if credit.amount > 0 {
+                pending.push_back(credit);
+            }
+        }
+        Self::set_pending(env, developer, pending, remaining - amount);
+        let accrued = Self::stored_accrued(env, developer);
+        let from_accrued = remaining.min(accrued);
+        Self::set_accrued(env, developer, accrued - from_accrued);
+
+        taken_open += from_accrued.min(matured_open);
+        Self::add_epoch_credit(env, developer, -taken_open.min(open_credit));
+    }
+
+    /// Sum of the entries of `pending` credited in `epoch`.
+    fn sum_epoch(pending: &Vec<PendingCredit>, epoch: u64) -> i128 {
+        let mut sum = 0;
+        for credit in pending.iter() {
+            if credit.epoch == epoch {
+                sum += credit.amount;
+            }
+        }
+        sum
+    }
+
+    /// Pool-wide pending credits stored for the open epoch `epoch` (see `OPEN_PENDING_KEY`).
+    fn get_open_pending(env: &Env, epoch: u64) -> i128 {
+        let (stored_epoch, amount): (u64, i128) = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(env, OPEN_PENDING_KEY))
+            .unwrap_or((epoch, 0));
+        if stored_epoch == epoch {
+            amount
+        } else {
+            0
+        }
+    }
+
+    /// Sum of `developer`'s credits from epoch `epoch` still within their holdback.
+    fn open_pending(env: &Env, developer: &Address, epoch: u64) -> i128 {
+        let now = env.ledger().sequence();
+        let mut pending = 0;
+        for credit in Self::get_pending(env, developer).iter() {
+            if credit.epoch == epoch && credit.matures_at > now {
+                pending += credit.amount;
+            }
+        }
+        pending
+    }
+
+    /// The open epoch (epoch 0 for pools predating epochs).
+    fn get_current_epoch(env: &Env) -> Epoch {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(env, CURRENT_EPOCH_KEY))
+            .unwrap_or(Epoch {
+                id: 0,
+                opened_at: 0,
+                closed_at: None,
+                total_credited: 0,
+            })
+    }
+
+    /// Add `delta` to `developer`'s credit and the total for the open epoch.
+    fn add_epoch_credit(env: &Env, developer: &Address, delta: i128) {
+        if delta == 0 {
+            return;
+        }
+        let mut current = Self::get_current_epoch(env);
+        current.total_credited += delta;
+        env.storage()
+            .instance()
+            .set(&Symbol::new(env, CURRENT_EPOCH_KEY), &current);
+        let credit = Self::get_epoch_credit(env.clone(), current.id, developer.clone()) + delta;
+        let key = (
+            Symbol::new(env, EPOCH_CREDIT_KEY),
+            current.id,
+            developer.clone(),
+        );
+        env.storage().persistent().set(&key, &credit);
+        Self::extend_persistent_ttl(env, &key);
+    }
+
+    /// Read an instance counter (0 if unset).
+    fn get_total(env: &Env, key: &str) -> i128 {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(env, key))
+            .unwrap_or(0)
+    }
+
+    /// Add `delta` to an instance counter.
+    fn add_to_total(env: &Env, key: &str, delta: i128) {
+        let total = Self::get_total(env, key) + delta;
+        env.storage().instance().set(&Symbol::new(env, key), &total);
+    }
+
+    /// Count `amount` paid out to `recipient` in the per-recipient and overall totals.
+    fn record_payout(env: &Env, recipient: &Address, amount: i128) {
+        let key = (Symbol::new(env, DISTRIBUTED_KEY), recipient.clone());
+        let distributed = Self::get_distributed(env.clone(), recipient.clone()) + amount;
+        env.storage().persistent().set(&key, &distributed);
+        Self::extend_persistent_ttl(env, &key);
+        Self::add_to_total(env, TOTAL_DISTRIBUTED_KEY, amount);
+    }
+
+    /// Sum of all developers' accrued and pending balances.
+    fn get_total_accrued(env: &Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(env, TOTAL_ACCRUED_KEY))
+            .unwrap_or(0)
+    }
+
+    /// Pool USDC that is neither frozen for a dispute nor owed to a developer.
+    fn unallocated(env: &Env, usdc: &token::Client) -> i128 {
         usdc.balance(&env.current_contract_address())
+            - Self::get_total_frozen(env)
+            - Self::get_total_accrued(env)
+    }
+
+    /// Sum of USDC frozen across all vaults.
+    fn get_total_frozen(env: &Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(env, TOTAL_FROZEN_KEY))
+            .unwrap_or(0)
+    }
+
+    /// Extend a persistent entry's TTL per the configured policy.
+    fn extend_persistent_ttl<K: IntoVal<Env, Val>>(env: &Env, key: &K) {
+        let config = Self::get_ttl_config(env.clone());
+        env.storage()
+            .persistent()
+            .extend_ttl(key, config.threshold, config.extend_to);
+    }
+
+    /// Return the configured USDC token address.
+    fn get_usdc(env: &Env) -> Result<Address, PoolError> {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(env, USDC_KEY))
+            .ok_or(PoolError::NotInitialized)
     }
 }
 
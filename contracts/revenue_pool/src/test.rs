@@ -1,8 +1,9 @@
 extern crate std;
 
 use super::*;
-use soroban_sdk::testutils::{Address as _, Events as _};
-use soroban_sdk::token;
+use soroban_sdk::testutils::storage::{Instance as _, Persistent as _};
+use soroban_sdk::testutils::{Address as _, Events as _, Ledger as _};
+use soroban_sdk::{token, IntoVal, Symbol};
 
 fn create_usdc<'a>(
     env: &'a Env,
@@ -39,8 +40,7 @@ fn init_success() {
 }
 
 #[test]
-#[should_panic(expected = "revenue pool already initialized")]
-fn init_double_panics() {
+fn init_double_fails() {
     let env = Env::default();
     env.mock_all_auths();
     let admin = Address::generate(&env);
@@ -48,7 +48,10 @@ fn init_double_panics() {
     let (usdc, _, _) = create_usdc(&env, &admin);
 
     client.init(&admin, &usdc);
-    client.init(&admin, &usdc);
+    assert_eq!(
+        client.try_init(&admin, &usdc),
+        Err(Ok(PoolError::AlreadyInitialized))
+    );
 }
 
 #[test]
@@ -69,8 +72,47 @@ fn distribute_success() {
 }
 
 #[test]
-#[should_panic(expected = "amount must be positive")]
-fn distribute_zero_panics() {
+fn batch_distribute_checks_total_once() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let dev_a = Address::generate(&env);
+    let dev_b = Address::generate(&env);
+    let (pool_addr, client) = create_pool(&env);
+    let (usdc_address, usdc_client, usdc_admin) = create_usdc(&env, &admin);
+
+    client.init(&admin, &usdc_address);
+    fund_pool(&usdc_admin, &pool_addr, 1_000);
+
+    let too_much = soroban_sdk::vec![&env, (dev_a.clone(), 600), (dev_b.clone(), 401)];
+    assert_eq!(
+        client.try_batch_distribute(&admin, &too_much),
+        Err(Ok(PoolError::InsufficientUsdc))
+    );
+    let with_zero = soroban_sdk::vec![&env, (dev_a.clone(), 600), (dev_b.clone(), 0)];
+    assert_eq!(
+        client.try_batch_distribute(&admin, &with_zero),
+        Err(Ok(PoolError::InvalidAmount))
+    );
+    let mut oversized = Vec::new(&env);
+    for _ in 0..=MAX_DISTRIBUTE_BATCH {
+        oversized.push_back((dev_a.clone(), 1));
+    }
+    assert_eq!(
+        client.try_batch_distribute(&admin, &oversized),
+        Err(Ok(PoolError::BatchTooLarge))
+    );
+    assert_eq!(usdc_client.balance(&pool_addr), 1_000);
+
+    let payouts = soroban_sdk::vec![&env, (dev_a.clone(), 600), (dev_b.clone(), 400)];
+    assert_eq!(client.batch_distribute(&admin, &payouts), 1_000);
+    assert_eq!(usdc_client.balance(&dev_a), 600);
+    assert_eq!(usdc_client.balance(&dev_b), 400);
+    assert_eq!(client.balance(), 0);
+}
+
+#[test]
+fn distribute_zero_fails() {
     let env = Env::default();
     env.mock_all_auths();
     let admin = Address::generate(&env);
@@ -79,12 +121,14 @@ fn distribute_zero_panics() {
     let (usdc, _, _) = create_usdc(&env, &admin);
 
     client.init(&admin, &usdc);
-    client.distribute(&admin, &developer, &0);
+    assert_eq!(
+        client.try_distribute(&admin, &developer, &0),
+        Err(Ok(PoolError::InvalidAmount))
+    );
 }
 
 #[test]
-#[should_panic(expected = "insufficient USDC balance")]
-fn distribute_excess_panics() {
+fn distribute_excess_fails() {
     let env = Env::default();
     env.mock_all_auths();
     let admin = Address::generate(&env);
@@ -94,12 +138,14 @@ fn distribute_excess_panics() {
 
     client.init(&admin, &usdc_address);
     fund_pool(&usdc_admin, &pool_addr, 100);
-    client.distribute(&admin, &developer, &101);
+    assert_eq!(
+        client.try_distribute(&admin, &developer, &101),
+        Err(Ok(PoolError::InsufficientUsdc))
+    );
 }
 
 #[test]
-#[should_panic(expected = "unauthorized: caller is not admin")]
-fn distribute_unauthorized_panics() {
+fn distribute_unauthorized_fails() {
     let env = Env::default();
     env.mock_all_auths();
     let admin = Address::generate(&env);
@@ -110,11 +156,14 @@ fn distribute_unauthorized_panics() {
 
     client.init(&admin, &usdc_address);
     fund_pool(&usdc_admin, &pool_addr, 500);
-    client.distribute(&attacker, &developer, &100);
+    assert_eq!(
+        client.try_distribute(&attacker, &developer, &100),
+        Err(Ok(PoolError::Unauthorized))
+    );
 }
 
 #[test]
-fn set_admin_transfers_control() {
+fn admin_handover_transfers_control() {
     let env = Env::default();
     env.mock_all_auths();
     let admin = Address::generate(&env);
@@ -125,8 +174,11 @@ fn set_admin_transfers_control() {
 
     client.init(&admin, &usdc_address);
     fund_pool(&usdc_admin, &pool_addr, 300);
-    client.set_admin(&admin, &new_admin);
+    client.propose_admin(&admin, &new_admin, &100);
+    assert_eq!(client.get_admin(), admin);
+    assert_eq!(client.accept_admin(), new_admin);
     assert_eq!(client.get_admin(), new_admin);
+    assert_eq!(client.get_pending_admin(), None);
 
     client.distribute(&new_admin, &developer, &100);
     assert_eq!(usdc_client.balance(&developer), 100);
@@ -137,11 +189,1084 @@ fn receive_payment_emits_event() {
     let env = Env::default();
     env.mock_all_auths();
     let admin = Address::generate(&env);
+    let (pool_addr, client) = create_pool(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &admin);
+
+    client.init(&admin, &usdc);
+    fund_pool(&usdc_admin, &pool_addr, 500);
+    client.receive_payment(&admin, &500, &false);
+    let events = env.events().all();
+    assert!(!events.is_empty());
+    assert_eq!(client.get_received(&admin), 500);
+}
+
+#[test]
+fn receive_payment_requires_trusted_source_and_funds() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let vault = Address::generate(&env);
+    let (pool_addr, client) = create_pool(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &admin);
+
+    client.init(&admin, &usdc);
+    fund_pool(&usdc_admin, &pool_addr, 300);
+    assert_eq!(
+        client.try_receive_payment(&vault, &300, &true),
+        Err(Ok(PoolError::UnknownSource))
+    );
+    assert_eq!(
+        client.try_receive_payment(&admin, &300, &true),
+        Err(Ok(PoolError::UnknownSource))
+    );
+
+    client.add_vault(&admin, &vault);
+    assert!(client.is_trusted_vault(&vault));
+    assert_eq!(
+        client.try_receive_payment(&vault, &301, &true),
+        Err(Ok(PoolError::PaymentNotReceived))
+    );
+    client.receive_payment(&vault, &200, &true);
+    // The checkpoint moved past the whole inflow; nothing is left to report.
+    assert_eq!(
+        client.try_receive_payment(&vault, &100, &true),
+        Err(Ok(PoolError::PaymentNotReceived))
+    );
+    fund_pool(&usdc_admin, &pool_addr, 50);
+    client.receive_payment(&vault, &50, &true);
+    assert_eq!(client.get_received(&vault), 250);
+    assert_eq!(client.get_refundable(&vault), 250);
+
+    client.remove_vault(&admin, &vault);
+    fund_pool(&usdc_admin, &pool_addr, 50);
+    assert_eq!(
+        client.try_receive_payment(&vault, &50, &true),
+        Err(Ok(PoolError::UnknownSource))
+    );
+}
+
+#[test]
+fn get_admin_before_init_fails() {
+    let env = Env::default();
+    let (_, client) = create_pool(&env);
+
+    assert_eq!(client.try_get_admin(), Err(Ok(PoolError::NotInitialized)));
+    assert_eq!(client.try_balance(), Err(Ok(PoolError::NotInitialized)));
+}
+
+#[test]
+fn pause_blocks_distribute_until_unpaused() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let developer = Address::generate(&env);
+    let (pool_addr, client) = create_pool(&env);
+    let (usdc_address, usdc_client, usdc_admin) = create_usdc(&env, &admin);
+
+    client.init(&admin, &usdc_address);
+    fund_pool(&usdc_admin, &pool_addr, 500);
+
+    let ops = PauseState {
+        distribute: true,
+        ..PauseState::default()
+    };
+    let state = client.pause(&admin, &ops);
+    assert!(state.distribute);
+    assert_eq!(client.get_pause_state(), ops);
+    assert_eq!(
+        client.try_distribute(&admin, &developer, &100),
+        Err(Ok(PoolError::Paused))
+    );
+
+    client.unpause(&admin, &ops);
+    assert_eq!(client.get_pause_state(), PauseState::default());
+    client.distribute(&admin, &developer, &100);
+    assert_eq!(usdc_client.balance(&developer), 100);
+}
+
+#[test]
+fn guardian_can_pause_but_not_unpause() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let guardian = Address::generate(&env);
+    let attacker = Address::generate(&env);
     let (_, client) = create_pool(&env);
     let (usdc, _, _) = create_usdc(&env, &admin);
 
     client.init(&admin, &usdc);
-    client.receive_payment(&admin, &500, &true);
+    client.set_guardian(&admin, &Some(guardian.clone()));
+    assert_eq!(client.get_guardian(), Some(guardian.clone()));
+
+    let ops = PauseState {
+        distribute: true,
+        ..PauseState::default()
+    };
+    assert_eq!(
+        client.try_pause(&attacker, &ops),
+        Err(Ok(PoolError::Unauthorized))
+    );
+    client.pause(&guardian, &ops);
     let events = env.events().all();
-    assert!(!events.is_empty());
+    let last_event = events.last().unwrap();
+    let topic0: Symbol = last_event.1.get(0).unwrap().into_val(&env);
+    assert_eq!(topic0, Symbol::new(&env, "pause"));
+    let data: PauseState = last_event.2.into_val(&env);
+    assert_eq!(data, ops);
+
+    assert_eq!(
+        client.try_unpause(&guardian, &ops),
+        Err(Ok(PoolError::Unauthorized))
+    );
+    assert!(client.get_pause_state().distribute);
+}
+
+#[test]
+fn admin_handover_expiry_and_cancel() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let new_admin = Address::generate(&env);
+    let (_, client) = create_pool(&env);
+    let (usdc, _, _) = create_usdc(&env, &admin);
+
+    client.init(&admin, &usdc);
+    assert_eq!(
+        client.try_propose_admin(&new_admin, &new_admin, &10),
+        Err(Ok(PoolError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_propose_admin(&admin, &new_admin, &0),
+        Err(Ok(PoolError::InvalidConfig))
+    );
+
+    client.propose_admin(&admin, &new_admin, &10);
+    env.ledger().with_mut(|li| li.sequence_number += 11);
+    assert_eq!(
+        client.try_accept_admin(),
+        Err(Ok(PoolError::TransferExpired))
+    );
+
+    client.cancel_admin_transfer(&admin);
+    assert_eq!(client.get_pending_admin(), None);
+    assert_eq!(
+        client.try_accept_admin(),
+        Err(Ok(PoolError::NoPendingTransfer))
+    );
+    assert_eq!(client.get_admin(), admin);
+}
+
+#[test]
+fn schema_version_and_upgrade_access() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let attacker = Address::generate(&env);
+    let (_, client) = create_pool(&env);
+    let (usdc, _, _) = create_usdc(&env, &admin);
+
+    client.init(&admin, &usdc);
+    assert_eq!(client.get_schema_version(), SCHEMA_VERSION);
+    assert_eq!(client.migrate(&admin), SCHEMA_VERSION);
+
+    let hash = BytesN::from_array(&env, &[7; 32]);
+    assert_eq!(
+        client.try_upgrade(&attacker, &hash),
+        Err(Ok(PoolError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_migrate(&attacker),
+        Err(Ok(PoolError::Unauthorized))
+    );
+}
+
+#[test]
+fn migrate_from_v1_seeds_checkpoint() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let vault = Address::generate(&env);
+    let (pool_addr, client) = create_pool(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &admin);
+
+    client.init(&admin, &usdc);
+    client.add_vault(&admin, &vault);
+    fund_pool(&usdc_admin, &pool_addr, 500);
+
+    // Rewind to a v1 pool, which kept no checkpoint: the 500 already held could otherwise
+    // be reported as a fresh payment.
+    env.as_contract(&pool_addr, || {
+        let inst = env.storage().instance();
+        inst.remove(&Symbol::new(&env, SCHEMA_VERSION_KEY));
+        inst.remove(&Symbol::new(&env, CHECKPOINT_KEY));
+    });
+    assert_eq!(client.get_schema_version(), 1);
+
+    assert_eq!(client.migrate(&admin), SCHEMA_VERSION);
+    assert_eq!(
+        client.try_receive_payment(&vault, &500, &true),
+        Err(Ok(PoolError::PaymentNotReceived))
+    );
+    fund_pool(&usdc_admin, &pool_addr, 100);
+    client.receive_payment(&vault, &100, &true);
+    assert_eq!(client.get_refundable(&vault), 100);
+}
+
+#[test]
+fn ttl_config_and_extend_ttl() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let (pool_addr, client) = create_pool(&env);
+    let (usdc, _, _) = create_usdc(&env, &admin);
+
+    client.init(&admin, &usdc);
+    let ttl = || env.as_contract(&pool_addr, || env.storage().instance().get_ttl());
+    assert_eq!(ttl(), DEFAULT_TTL_EXTEND_TO);
+
+    env.ledger().with_mut(|li| li.sequence_number += 600_000);
+    client.extend_ttl();
+    assert_eq!(ttl(), DEFAULT_TTL_EXTEND_TO);
+
+    let bad = TtlConfig {
+        threshold: 100,
+        extend_to: 50,
+    };
+    assert_eq!(
+        client.try_set_ttl_config(&admin, &bad),
+        Err(Ok(PoolError::InvalidConfig))
+    );
+    let config = TtlConfig {
+        threshold: 1_500_000,
+        extend_to: 2_000_000,
+    };
+    client.set_ttl_config(&admin, &config);
+    assert_eq!(client.get_ttl_config(), config);
+    assert_eq!(ttl(), 2_000_000);
+}
+
+#[test]
+fn receive_payment_extends_vault_entries() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let vault = Address::generate(&env);
+    let developer = Address::generate(&env);
+    let (pool_addr, client) = create_pool(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &admin);
+
+    client.init(&admin, &usdc);
+    let config = TtlConfig {
+        threshold: 5_000,
+        extend_to: 6_000,
+    };
+    client.set_ttl_config(&admin, &config);
+    client.add_vault(&admin, &vault);
+    client.set_vault_developer(&admin, &vault, &Some(developer.clone()));
+    let ttls = || {
+        env.as_contract(&pool_addr, || {
+            let store = env.storage().persistent();
+            (
+                store.get_ttl(&(Symbol::new(&env, TRUSTED_VAULT_KEY), vault.clone())),
+                store.get_ttl(&(Symbol::new(&env, DEVELOPER_KEY), vault.clone())),
+            )
+        })
+    };
+
+    env.ledger().with_mut(|li| li.sequence_number += 3_000);
+    assert_eq!(ttls(), (3_000, 3_000));
+    fund_pool(&usdc_admin, &pool_addr, 100);
+    client.receive_payment(&vault, &100, &true);
+    assert_eq!(ttls(), (6_000, 6_000));
+    assert_eq!(client.accrued_of(&developer), 100);
+}
+
+#[test]
+fn refund_payment_limited_to_settled_inflows() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let vault = Address::generate(&env);
+    let (pool_addr, client) = create_pool(&env);
+    let (usdc_address, usdc_client, usdc_admin) = create_usdc(&env, &admin);
+
+    client.init(&admin, &usdc_address);
+    client.add_vault(&admin, &vault);
+    // Claiming a payment that never arrived credits nothing.
+    assert_eq!(
+        client.try_receive_payment(&vault, &500, &true),
+        Err(Ok(PoolError::PaymentNotReceived))
+    );
+    assert_eq!(client.get_refundable(&vault), 0);
+
+    fund_pool(&usdc_admin, &pool_addr, 300);
+    client.receive_payment(&vault, &300, &true);
+    assert_eq!(client.get_refundable(&vault), 300);
+    assert_eq!(
+        client.try_refund_payment(&vault, &301),
+        Err(Ok(PoolError::ExceedsRefundable))
+    );
+
+    client.refund_payment(&vault, &120);
+    assert_eq!(usdc_client.balance(&vault), 120);
+    assert_eq!(client.get_refundable(&vault), 180);
+}
+
+#[test]
+fn frozen_payments_cannot_be_distributed_or_refunded() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let vault = Address::generate(&env);
+    let developer = Address::generate(&env);
+    let (pool_addr, client) = create_pool(&env);
+    let (usdc_address, _, usdc_admin) = create_usdc(&env, &admin);
+
+    client.init(&admin, &usdc_address);
+    client.add_vault(&admin, &vault);
+    fund_pool(&usdc_admin, &pool_addr, 300);
+    client.receive_payment(&vault, &300, &true);
+
+    assert_eq!(
+        client.try_freeze_payment(&vault, &301),
+        Err(Ok(PoolError::ExceedsRefundable))
+    );
+    client.freeze_payment(&vault, &200);
+    assert_eq!(client.get_frozen(&vault), 200);
+    assert_eq!(
+        client.try_distribute(&admin, &developer, &101),
+        Err(Ok(PoolError::InsufficientUsdc))
+    );
+    assert_eq!(
+        client.try_refund_payment(&vault, &101),
+        Err(Ok(PoolError::ExceedsRefundable))
+    );
+
+    assert_eq!(
+        client.try_unfreeze_payment(&vault, &201),
+        Err(Ok(PoolError::ExceedsRefundable))
+    );
+    client.unfreeze_payment(&vault, &200);
+    client.distribute(&admin, &developer, &300);
+}
+
+#[test]
+fn developer_claims_admin_credit() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let developer = Address::generate(&env);
+    let other = Address::generate(&env);
+    let (pool_addr, client) = create_pool(&env);
+    let (usdc_address, usdc_client, usdc_admin) = create_usdc(&env, &admin);
+
+    client.init(&admin, &usdc_address);
+    fund_pool(&usdc_admin, &pool_addr, 500);
+    assert_eq!(
+        client.try_credit(&admin, &developer, &501),
+        Err(Ok(PoolError::InsufficientUsdc))
+    );
+    assert_eq!(
+        client.try_credit(&developer, &developer, &100),
+        Err(Ok(PoolError::Unauthorized))
+    );
+    client.credit(&admin, &developer, &400);
+    assert_eq!(client.accrued_of(&developer), 400);
+
+    // Credited funds are earmarked: the admin can only push out the rest.
+    assert_eq!(
+        client.try_distribute(&admin, &other, &101),
+        Err(Ok(PoolError::InsufficientUsdc))
+    );
+
+    client.close_epoch(&admin);
+    assert_eq!(
+        client.try_claim(&developer, &401),
+        Err(Ok(PoolError::ExceedsAccrued))
+    );
+    assert_eq!(client.claim(&developer, &150), 250);
+    let events = env.events().all();
+    let last_event = events.last().expect("expected claim event");
+    let topic0: Symbol = last_event.1.get(0).unwrap().into_val(&env);
+    let data: i128 = last_event.2.into_val(&env);
+    assert_eq!(topic0, Symbol::new(&env, "claim"));
+    assert_eq!(data, 150);
+    assert_eq!(usdc_client.balance(&developer), 150);
+
+    let ops = PauseState {
+        claim: true,
+        ..PauseState::default()
+    };
+    client.pause(&admin, &ops);
+    assert_eq!(
+        client.try_claim(&developer, &250),
+        Err(Ok(PoolError::Paused))
+    );
+    client.unpause(&admin, &ops);
+    client.claim(&developer, &250);
+    assert_eq!(client.accrued_of(&developer), 0);
+    assert_eq!(client.balance(), 100);
+}
+
+#[test]
+fn vault_settlements_accrue_to_mapped_developer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let vault = Address::generate(&env);
+    let developer = Address::generate(&env);
+    let (pool_addr, client) = create_pool(&env);
+    let (usdc_address, _, usdc_admin) = create_usdc(&env, &admin);
+
+    client.init(&admin, &usdc_address);
+    client.add_vault(&admin, &vault);
+    client.set_vault_developer(&admin, &vault, &Some(developer.clone()));
+    let events = env.events().all();
+    let last_event = events.last().expect("expected vault_developer_set event");
+    let topic0: Symbol = last_event.1.get(0).unwrap().into_val(&env);
+    let data: (Address, Option<Address>, Option<Address>) = last_event.2.into_val(&env);
+    assert_eq!(topic0, Symbol::new(&env, "vault_developer_set"));
+    assert_eq!(data, (vault.clone(), None, Some(developer.clone())));
+    assert_eq!(client.get_vault_developer(&vault), Some(developer.clone()));
+    fund_pool(&usdc_admin, &pool_addr, 300);
+    client.receive_payment(&vault, &300, &true);
+    assert_eq!(client.accrued_of(&developer), 300);
+
+    // Disputed and refunded funds are taken out of the developer's balance.
+    client.freeze_payment(&vault, &100);
+    assert_eq!(client.accrued_of(&developer), 200);
+    client.unfreeze_payment(&vault, &100);
+    client.refund_payment(&vault, &50);
+    assert_eq!(client.accrued_of(&developer), 250);
+
+    client.close_epoch(&admin);
+    client.claim(&developer, &250);
+    assert_eq!(
+        client.try_refund_payment(&vault, &1),
+        Err(Ok(PoolError::InsufficientUsdc))
+    );
+}
+
+#[test]
+fn settle_credits_payees_by_split() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let dev_a = Address::generate(&env);
+    let dev_b = Address::generate(&env);
+    let (pool_addr, client) = create_pool(&env);
+    let (usdc_address, _, usdc_admin) = create_usdc(&env, &admin);
+    let api_id = Symbol::new(&env, "weather");
+
+    client.init(&admin, &usdc_address);
+    fund_pool(&usdc_admin, &pool_addr, 1_000);
+    assert_eq!(
+        client.try_settle(&admin, &api_id, &100),
+        Err(Ok(PoolError::SplitNotFound))
+    );
+
+    let bad = soroban_sdk::vec![
+        &env,
+        Split {
+            payee: dev_a.clone(),
+            bps: 7_000,
+        },
+        Split {
+            payee: dev_b.clone(),
+            bps: 2_999,
+        }
+    ];
+    assert_eq!(
+        client.try_set_split(&admin, &api_id, &bad),
+        Err(Ok(PoolError::InvalidConfig))
+    );
+    let splits = soroban_sdk::vec![
+        &env,
+        Split {
+            payee: dev_a.clone(),
+            bps: 6_667,
+        },
+        Split {
+            payee: dev_b.clone(),
+            bps: 3_333,
+        }
+    ];
+    client.set_split(&admin, &api_id, &splits);
+    let events = env.events().all();
+    let last_event = events.last().expect("expected split_set event");
+    let topic0: Symbol = last_event.1.get(0).unwrap().into_val(&env);
+    assert_eq!(topic0, Symbol::new(&env, "split_set"));
+    assert_eq!(client.get_split(&api_id), Some(splits));
+
+    // 6_667 / 3_333 of 100 is 66.67 / 33.33: the rounding remainder goes to the first payee.
+    client.settle(&admin, &api_id, &100);
+    assert_eq!(client.accrued_of(&dev_a), 67);
+    assert_eq!(client.accrued_of(&dev_b), 33);
+    assert_eq!(
+        client.try_settle(&admin, &api_id, &901),
+        Err(Ok(PoolError::InsufficientUsdc))
+    );
+
+    client.remove_split(&admin, &api_id);
+    assert_eq!(client.get_split(&api_id), None);
+    assert_eq!(
+        client.try_remove_split(&admin, &api_id),
+        Err(Ok(PoolError::SplitNotFound))
+    );
+}
+
+#[test]
+fn holdback_delays_claims_until_maturity() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let vault = Address::generate(&env);
+    let developer = Address::generate(&env);
+    let other = Address::generate(&env);
+    let (pool_addr, client) = create_pool(&env);
+    let (usdc_address, _, usdc_admin) = create_usdc(&env, &admin);
+
+    client.init(&admin, &usdc_address);
+    client.set_holdback(&admin, &100);
+    assert_eq!(client.get_holdback(), 100);
+    client.add_vault(&admin, &vault);
+    client.set_vault_developer(&admin, &vault, &Some(developer.clone()));
+    fund_pool(&usdc_admin, &pool_addr, 300);
+    client.receive_payment(&vault, &300, &true);
+    assert_eq!(client.pending_of(&developer), 300);
+    assert_eq!(client.accrued_of(&developer), 0);
+    assert_eq!(
+        client.try_claim(&developer, &1),
+        Err(Ok(PoolError::ExceedsAccrued))
+    );
+    // Pending revenue is earmarked and cannot be pushed out by the admin either.
+    assert_eq!(
+        client.try_distribute(&admin, &other, &1),
+        Err(Ok(PoolError::InsufficientUsdc))
+    );
+
+    // Refunds during the holdback come out of the pending bucket.
+    client.refund_payment(&vault, &50);
+    assert_eq!(client.pending_of(&developer), 250);
+
+    env.ledger().with_mut(|li| li.sequence_number += 100);
+    assert_eq!(client.pending_of(&developer), 0);
+    assert_eq!(client.accrued_of(&developer), 250);
+    client.close_epoch(&admin);
+    client.claim(&developer, &100);
+    assert_eq!(client.accrued_of(&developer), 150);
+
+    // The admin can release a pending credit early.
+    fund_pool(&usdc_admin, &pool_addr, 40);
+    client.credit(&admin, &developer, &40);
+    assert_eq!(client.pending_of(&developer), 40);
+    assert_eq!(client.release_pending(&admin, &developer), 40);
+    assert_eq!(client.pending_of(&developer), 0);
+    client.close_epoch(&admin);
+    assert_eq!(client.claim(&developer, &190), 0);
+}
+
+#[test]
+fn multisig_payout_requires_threshold() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let s1 = Address::generate(&env);
+    let s2 = Address::generate(&env);
+    let s3 = Address::generate(&env);
+    let developer = Address::generate(&env);
+    let (pool_addr, client) = create_pool(&env);
+    let (usdc_address, usdc_client, usdc_admin) = create_usdc(&env, &admin);
+
+    client.init(&admin, &usdc_address);
+    fund_pool(&usdc_admin, &pool_addr, 1_000);
+    let signers = soroban_sdk::vec![&env, s1.clone(), s2.clone(), s3.clone()];
+    assert_eq!(
+        client.try_set_signers(&admin, &signers, &4),
+        Err(Ok(PoolError::InvalidConfig))
+    );
+    client.set_signers(&admin, &signers, &2);
+    assert_eq!(client.get_threshold(), 2);
+
+    // The admin can no longer move funds alone, nor replace the signer set.
+    assert_eq!(
+        client.try_distribute(&admin, &developer, &100),
+        Err(Ok(PoolError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_set_signers(&admin, &signers, &1),
+        Err(Ok(PoolError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_propose_payout(&admin, &developer, &100, &100),
+        Err(Ok(PoolError::Unauthorized))
+    );
+
+    let id = client.propose_payout(&s1, &developer, &400, &100);
+    assert_eq!(client.try_execute(&id), Err(Ok(PoolError::ThresholdNotMet)));
+    assert_eq!(
+        client.try_approve(&s1, &id),
+        Err(Ok(PoolError::AlreadyApproved))
+    );
+    assert_eq!(client.approve(&s2, &id), 2);
+    client.execute(&id);
+    assert_eq!(usdc_client.balance(&developer), 400);
+    assert_eq!(client.get_proposal(&id), None);
+    assert_eq!(
+        client.try_execute(&id),
+        Err(Ok(PoolError::ProposalNotFound))
+    );
+
+    let late = client.propose_payout(&s1, &developer, &100, &10);
+    env.ledger().with_mut(|li| li.sequence_number += 11);
+    assert_eq!(
+        client.try_approve(&s2, &late),
+        Err(Ok(PoolError::ProposalExpired))
+    );
+}
+
+#[test]
+fn multisig_signer_change_needs_approval() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let s1 = Address::generate(&env);
+    let s2 = Address::generate(&env);
+    let s3 = Address::generate(&env);
+    let developer = Address::generate(&env);
+    let (pool_addr, client) = create_pool(&env);
+    let (usdc_address, _, usdc_admin) = create_usdc(&env, &admin);
+
+    client.init(&admin, &usdc_address);
+    fund_pool(&usdc_admin, &pool_addr, 1_000);
+    client.set_signers(&admin, &soroban_sdk::vec![&env, s1.clone(), s2.clone()], &2);
+
+    let payout = client.propose_payout(&s2, &developer, &100, &100);
+    let rotate = client.propose_signers(
+        &s1,
+        &soroban_sdk::vec![&env, s1.clone(), s3.clone()],
+        &2,
+        &100,
+    );
+    client.approve(&s2, &rotate);
+    client.execute(&rotate);
+    assert_eq!(
+        client.get_signers(),
+        soroban_sdk::vec![&env, s1.clone(), s3.clone()]
+    );
+
+    // s2's approval no longer counts once s2 is removed from the set.
+    client.approve(&s1, &payout);
+    assert_eq!(
+        client.try_execute(&payout),
+        Err(Ok(PoolError::ThresholdNotMet))
+    );
+    client.approve(&s3, &payout);
+    client.execute(&payout);
+}
+
+#[test]
+fn stats_track_every_fund_movement() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let vault = Address::generate(&env);
+    let dev_a = Address::generate(&env);
+    let dev_b = Address::generate(&env);
+    let (pool_addr, client) = create_pool(&env);
+    let (usdc_address, _, usdc_admin) = create_usdc(&env, &admin);
+
+    client.init(&admin, &usdc_address);
+    client.add_vault(&admin, &vault);
+    client.set_vault_developer(&admin, &vault, &Some(dev_a.clone()));
+    client.set_holdback(&admin, &100);
+    fund_pool(&usdc_admin, &pool_addr, 1_000);
+    client.receive_payment(&vault, &1_000, &true);
+    client.refund_payment(&vault, &100);
+    client.freeze_payment(&vault, &50);
+    client.release_pending(&admin, &dev_a);
+    client.close_epoch(&admin);
+    client.claim(&dev_a, &300);
+    client.set_holdback(&admin, &0);
+    client.unfreeze_payment(&vault, &50);
+    // Everything left is owed to dev_a; top up before crediting dev_b.
+    fund_pool(&usdc_admin, &pool_addr, 300);
+    client.credit(&admin, &dev_b, &100);
+    client.distribute(&admin, &dev_b, &200);
+
+    assert_eq!(client.get_received(&vault), 1_000);
+    assert_eq!(client.get_distributed(&dev_a), 300);
+    assert_eq!(client.get_distributed(&dev_b), 200);
+    assert_eq!(
+        client.get_stats(),
+        PoolStats {
+            balance: 700,
+            total_received: 1_000,
+            total_distributed: 500,
+            total_refunded: 100,
+            frozen: 0,
+            open_epoch: 150,
+            closed_epochs: 550,
+            pending: 0,
+            claimable: 550,
+        }
+    );
+
+    // Held-back credits count as pending and stay out of claimable after their epoch closes.
+    client.set_holdback(&admin, &100);
+    fund_pool(&usdc_admin, &pool_addr, 40);
+    client.credit(&admin, &dev_b, &40);
+    let stats = client.get_stats();
+    assert_eq!(
+        (
+            stats.open_epoch,
+            stats.closed_epochs,
+            stats.pending,
+            stats.claimable
+        ),
+        (190, 550, 40, 550)
+    );
+    client.close_epoch(&admin);
+    let stats = client.get_stats();
+    assert_eq!(
+        (
+            stats.open_epoch,
+            stats.closed_epochs,
+            stats.pending,
+            stats.claimable
+        ),
+        (0, 740, 40, 700)
+    );
+    env.ledger().with_mut(|li| li.sequence_number += 100);
+    client.claim(&dev_b, &140);
+    let stats = client.get_stats();
+    assert_eq!((stats.pending, stats.claimable), (0, 600));
+}
+
+#[test]
+fn claims_limited_to_closed_epochs() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let developer = Address::generate(&env);
+    let (pool_addr, client) = create_pool(&env);
+    let (usdc_address, _, usdc_admin) = create_usdc(&env, &admin);
+
+    client.init(&admin, &usdc_address);
+    fund_pool(&usdc_admin, &pool_addr, 1_000);
+    client.credit(&admin, &developer, &300);
+    assert_eq!(client.accrued_of(&developer), 300);
+    assert_eq!(client.claimable_of(&developer), 0);
+    assert_eq!(
+        client.try_claim(&developer, &1),
+        Err(Ok(PoolError::ExceedsAccrued))
+    );
+
+    env.ledger().with_mut(|li| li.sequence_number += 10);
+    let closed = client.close_epoch(&admin);
+    assert_eq!(closed.id, 0);
+    assert_eq!(closed.closed_at, Some(env.ledger().sequence()));
+    assert_eq!(closed.total_credited, 300);
+    let events = env.events().all();
+    let last_event = events.last().expect("expected epoch_opened event");
+    let topic0: Symbol = last_event.1.get(0).unwrap().into_val(&env);
+    let topic1: u64 = last_event.1.get(1).unwrap().into_val(&env);
+    assert_eq!(topic0, Symbol::new(&env, "epoch_opened"));
+    assert_eq!(topic1, 1);
+
+    // Credits in the new epoch wait for the next close; epoch 0's snapshot is unchanged.
+    client.credit(&admin, &developer, &200);
+    assert_eq!(client.claimable_of(&developer), 300);
+    assert_eq!(client.get_current_epoch_id(), 1);
+    assert_eq!(client.get_epoch_credit(&0, &developer), 300);
+    assert_eq!(client.get_epoch_credit(&1, &developer), 200);
+    assert_eq!(client.get_epoch(&0), Some(closed));
+    assert_eq!(client.get_epoch(&1).unwrap().closed_at, None);
+    assert_eq!(client.get_epoch(&2), None);
+
+    client.claim(&developer, &300);
+    assert_eq!(
+        client.try_claim(&developer, &200),
+        Err(Ok(PoolError::ExceedsAccrued))
+    );
+    client.close_epoch(&admin);
+    client.claim(&developer, &200);
+}
+
+#[test]
+fn held_back_closed_epoch_credit_does_not_unlock_open_epoch() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let developer = Address::generate(&env);
+    let (pool_addr, client) = create_pool(&env);
+    let (usdc_address, _, usdc_admin) = create_usdc(&env, &admin);
+
+    client.init(&admin, &usdc_address);
+    fund_pool(&usdc_admin, &pool_addr, 1_000);
+    client.set_holdback(&admin, &1_000);
+    client.credit(&admin, &developer, &100);
+    client.close_epoch(&admin);
+    client.set_holdback(&admin, &0);
+    client.credit(&admin, &developer, &50);
+
+    // Epoch 0's 100 is still held back and epoch 1's 50 is not closed yet.
+    assert_eq!(client.accrued_of(&developer), 50);
+    assert_eq!(client.claimable_of(&developer), 0);
+    assert_eq!(
+        client.try_claim(&developer, &50),
+        Err(Ok(PoolError::ExceedsAccrued))
+    );
+
+    env.ledger().with_mut(|li| li.sequence_number += 1_000);
+    assert_eq!(client.claimable_of(&developer), 100);
+    client.close_epoch(&admin);
+    assert_eq!(client.claimable_of(&developer), 150);
+}
+
+#[test]
+fn pending_credits_merge_and_stay_bounded() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let developer = Address::generate(&env);
+    let (pool_addr, client) = create_pool(&env);
+    let (usdc_address, _, usdc_admin) = create_usdc(&env, &admin);
+    let pending_len = || -> u32 {
+        env.as_contract(&pool_addr, || {
+            env.storage()
+                .persistent()
+                .get::<_, Vec<PendingCredit>>(&(Symbol::new(&env, PENDING_KEY), developer.clone()))
+                .map_or(0, |p| p.len())
+        })
+    };
+
+    client.init(&admin, &usdc_address);
+    client.set_holdback(&admin, &1_000);
+    fund_pool(&usdc_admin, &pool_addr, 10_000);
+
+    // Credits maturing on the same ledger share one entry.
+    client.credit(&admin, &developer, &10);
+    client.credit(&admin, &developer, &10);
+    assert_eq!(pending_len(), 1);
+
+    for _ in 0..MAX_PENDING_CREDITS + 8 {
+        env.ledger().with_mut(|li| li.sequence_number += 1);
+        client.credit(&admin, &developer, &10);
+    }
+    assert_eq!(pending_len(), MAX_PENDING_CREDITS);
+    assert_eq!(client.pending_of(&developer), 420);
+
+    // Crediting after maturity folds the matured entries into the claimable balance first.
+    env.ledger().with_mut(|li| li.sequence_number += 1_000);
+    client.credit(&admin, &developer, &10);
+    assert_eq!(pending_len(), 1);
+    assert_eq!(client.pending_of(&developer), 10);
+    assert_eq!(client.accrued_of(&developer), 420);
+    assert_eq!(client.get_stats().open_epoch, 430);
+}
+
+#[test]
+fn pending_credits_stay_bounded_across_epochs() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let developer = Address::generate(&env);
+    let (pool_addr, client) = create_pool(&env);
+    let (usdc_address, _, usdc_admin) = create_usdc(&env, &admin);
+    let pending = || -> Vec<PendingCredit> {
+        env.as_contract(&pool_addr, || {
+            env.storage()
+                .persistent()
+                .get(&(Symbol::new(&env, PENDING_KEY), developer.clone()))
+                .unwrap_or(Vec::new(&env))
+        })
+    };
+
+    client.init(&admin, &usdc_address);
+    client.set_holdback(&admin, &1_000);
+    fund_pool(&usdc_admin, &pool_addr, 10_000);
+
+    // One credit per epoch, closing the epoch each time, keeps the list full of distinct
+    // closed-epoch entries that cannot be merged into the latest one.
+    for _ in 0..MAX_PENDING_CREDITS + 8 {
+        env.ledger().with_mut(|li| li.sequence_number += 1);
+        client.credit(&admin, &developer, &10);
+        client.close_epoch(&admin);
+    }
+    let list = pending();
+    assert_eq!(list.len(), MAX_PENDING_CREDITS);
+    assert_eq!(client.pending_of(&developer), 400);
+    assert_eq!(list.get(0).unwrap().amount, 90);
+    assert_eq!(
+        list.last().unwrap().epoch,
+        u64::from(MAX_PENDING_CREDITS) + 7
+    );
+
+    // A credit in the open epoch still lands in its own entry and stays unclaimable.
+    client.credit(&admin, &developer, &10);
+    assert_eq!(pending().len(), MAX_PENDING_CREDITS);
+    assert_eq!(client.pending_of(&developer), 410);
+
+    env.ledger().with_mut(|li| li.sequence_number += 1_000);
+    assert_eq!(client.accrued_of(&developer), 410);
+    assert_eq!(client.claimable_of(&developer), 400);
+}
+
+#[test]
+fn multisig_governs_revenue_routing() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let s1 = Address::generate(&env);
+    let s2 = Address::generate(&env);
+    let vault = Address::generate(&env);
+    let developer = Address::generate(&env);
+    let attacker = Address::generate(&env);
+    let (pool_addr, client) = create_pool(&env);
+    let (usdc_address, _, usdc_admin) = create_usdc(&env, &admin);
+    let api_id = Symbol::new(&env, "api");
+    let to_attacker = soroban_sdk::vec![
+        &env,
+        Split {
+            payee: attacker.clone(),
+            bps: BPS_DENOMINATOR,
+        }
+    ];
+
+    client.init(&admin, &usdc_address);
+    fund_pool(&usdc_admin, &pool_addr, 1_000);
+    client.set_signers(&admin, &soroban_sdk::vec![&env, s1.clone(), s2.clone()], &2);
+
+    // None of the admin paths that route revenue work alone once signers are set.
+    assert_eq!(
+        client.try_set_split(&admin, &api_id, &to_attacker),
+        Err(Ok(PoolError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_remove_split(&admin, &api_id),
+        Err(Ok(PoolError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_settle(&admin, &api_id, &100),
+        Err(Ok(PoolError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_set_vault_developer(&admin, &vault, &Some(attacker.clone())),
+        Err(Ok(PoolError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_set_holdback(&admin, &0),
+        Err(Ok(PoolError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_release_pending(&admin, &developer),
+        Err(Ok(PoolError::Unauthorized))
+    );
+
+    // The signers can still do all of it through proposals.
+    let to_developer = soroban_sdk::vec![
+        &env,
+        Split {
+            payee: developer.clone(),
+            bps: BPS_DENOMINATOR,
+        }
+    ];
+    assert_eq!(
+        client.try_propose(&s1, &ProposalAction::Settle(api_id.clone(), 0), &100),
+        Err(Ok(PoolError::InvalidAmount))
+    );
+    for action in [
+        ProposalAction::SetSplit(api_id.clone(), to_developer),
+        ProposalAction::Settle(api_id.clone(), 300),
+        ProposalAction::SetVaultDeveloper(vault.clone(), Some(developer.clone())),
+    ] {
+        let id = client.propose(&s1, &action, &100);
+        client.approve(&s2, &id);
+        client.execute(&id);
+    }
+    assert_eq!(client.accrued_of(&developer), 300);
+    assert_eq!(client.get_vault_developer(&vault), Some(developer));
+}
+
+#[test]
+fn multisig_governs_upgrade_admin_vaults_and_epochs() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let s1 = Address::generate(&env);
+    let s2 = Address::generate(&env);
+    let vault = Address::generate(&env);
+    let new_admin = Address::generate(&env);
+    let (pool_addr, client) = create_pool(&env);
+    let (usdc_address, _, _) = create_usdc(&env, &admin);
+
+    client.init(&admin, &usdc_address);
+    client.propose_admin(&admin, &s1, &100);
+    client.set_signers(&admin, &soroban_sdk::vec![&env, s1.clone(), s2.clone()], &2);
+
+    let hash = BytesN::from_array(&env, &[7; 32]);
+    assert_eq!(
+        client.try_upgrade(&admin, &hash),
+        Err(Ok(PoolError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_propose_admin(&admin, &admin, &100),
+        Err(Ok(PoolError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_cancel_admin_transfer(&admin),
+        Err(Ok(PoolError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_add_vault(&admin, &vault),
+        Err(Ok(PoolError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_remove_vault(&admin, &vault),
+        Err(Ok(PoolError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_close_epoch(&admin),
+        Err(Ok(PoolError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_propose(
+            &s1,
+            &ProposalAction::ProposeAdmin(new_admin.clone(), 0),
+            &100
+        ),
+        Err(Ok(PoolError::InvalidConfig))
+    );
+    client.propose(&s1, &ProposalAction::Upgrade(hash), &100);
+
+    for action in [
+        ProposalAction::AddVault(vault.clone()),
+        ProposalAction::CloseEpoch,
+        ProposalAction::ProposeAdmin(new_admin.clone(), 50),
+    ] {
+        let id = client.propose(&s1, &action, &100);
+        client.approve(&s2, &id);
+        client.execute(&id);
+    }
+    // The handover is attributed to the pool itself rather than to any single key.
+    let events = env.events().all();
+    let proposed = events
+        .iter()
+        .find(|e| {
+            let topic0: Symbol = e.1.get(0).unwrap().into_val(&env);
+            topic0 == Symbol::new(&env, "admin_proposed")
+        })
+        .unwrap();
+    let by: Address = proposed.1.get(1).unwrap().into_val(&env);
+    assert_eq!(by, pool_addr);
+    assert_eq!(client.get_pending_admin().unwrap().new_admin, new_admin);
+    assert!(client.is_trusted_vault(&vault));
+    assert_eq!(client.get_current_epoch_id(), 1);
+
+    assert_eq!(client.accept_admin(), new_admin);
+    assert_eq!(
+        client.try_close_epoch(&new_admin),
+        Err(Ok(PoolError::Unauthorized))
+    );
 }
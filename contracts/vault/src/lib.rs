@@ -1,6 +1,9 @@
 #![no_std]
 
-use soroban_sdk::{contract, contractimpl, contracttype, token, Address, Env, Symbol, Vec};
+use soroban_sdk::{
+    contract, contractclient, contracterror, contractimpl, contracttype, token, Address, BytesN,
+    Env, IntoVal, Map, Symbol, Val, Vec,
+};
 
 /// Single item for batch deduct: amount and optional request id for idempotency/tracking.
 #[contracttype]
@@ -8,15 +11,238 @@ use soroban_sdk::{contract, contractimpl, contracttype, token, Address, Env, Sym
 pub struct DeductItem {
     pub amount: i128,
     pub request_id: Option<Symbol>,
+    /// Sub-account to charge; `None` charges the vault owner's sub-account.
+    pub depositor: Option<Address>,
 }
 
 #[contracttype]
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct VaultMeta {
     pub owner: Address,
+    /// Aggregate of all depositor sub-accounts (see `balance_of`).
     pub balance: i128,
-    /// Minimum amount required per deposit; deposits below this panic.
+    /// Minimum amount required per deposit; smaller deposits fail with `BelowMinDeposit`.
+    pub min_deposit: i128,
+}
+
+/// Errors returned by `CalloraVault` entry points.
+///
+/// Codes are stable across releases; clients may match on the numeric value.
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum VaultError {
+    /// `init` was called on a vault that is already initialized.
+    AlreadyInitialized = 1,
+    /// The vault has not been initialized yet.
+    NotInitialized = 2,
+    /// The caller is not permitted to perform this operation.
+    Unauthorized = 3,
+    /// Amount is zero or negative.
+    InvalidAmount = 4,
+    /// The vault's ledger balance is lower than the requested amount.
+    InsufficientBalance = 5,
+    /// A single deduct exceeds the configured `max_deduct`.
+    ExceedsMaxDeduct = 6,
+    /// A deposit is below the configured `min_deposit`.
+    BelowMinDeposit = 7,
+    /// The contract holds less USDC than the operation requires.
+    InsufficientUsdc = 8,
+    /// A configuration value is out of range.
+    InvalidConfig = 9,
+    /// `batch_deduct` or `batch_distribute` was called with no items.
+    EmptyBatch = 10,
+    /// The request_id was already processed (or repeats within a batch).
+    DuplicateRequest = 11,
+    /// The address is not on the deducter allowlist.
+    DeducterNotFound = 12,
+    /// The operation is currently paused.
+    Paused = 13,
+    /// There is no pending ownership or admin transfer.
+    NoPendingTransfer = 14,
+    /// The pending transfer's acceptance window has passed.
+    TransferExpired = 15,
+    /// `distribute` asked for more than the vault's undistributed earned revenue.
+    ExceedsAccruedRevenue = 16,
+    /// No hold exists for the given hold_id.
+    HoldNotFound = 17,
+    /// The hold passed its `expires_at` ledger and was released.
+    HoldExpired = 18,
+    /// A hold with the same hold_id is already active.
+    DuplicateHold = 19,
+    /// The charge would push spending in the current window past the owner's cap.
+    BudgetExceeded = 20,
+    /// No deduct is recorded for the request_id (never charged, or outside the retention window).
+    RequestNotFound = 21,
+    /// Cumulative refunds would exceed the refundable (net of platform fee) part of the deduct.
+    ExceedsDeducted = 22,
+    /// The request_id already has an open dispute (or a refund was attempted while it is open).
+    DisputeOpen = 23,
+    /// The dispute window for this deduct has passed.
+    DisputeWindowClosed = 24,
+    /// No open dispute exists for the request_id.
+    DisputeNotFound = 25,
+    /// `batch_distribute` was called with more than `MAX_DISTRIBUTE_BATCH` payees.
+    BatchTooLarge = 26,
+    /// `expire_hold` was called before the hold's expiry ledger.
+    HoldActive = 27,
+    /// `hold` was called while `MAX_OPEN_HOLDS` unexpired holds are already open.
+    TooManyHolds = 28,
+    /// The code was upgraded but `migrate` has not yet brought storage to `SCHEMA_VERSION`.
+    MigrationPending = 29,
+}
+
+/// Ownership transfer proposed by the current owner and awaiting acceptance.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PendingOwner {
+    pub new_owner: Address,
+    /// Last ledger sequence at which `accept_ownership` succeeds; `None` = no deadline.
+    pub expires_at: Option<u32>,
+}
+
+/// Admin handover proposed by the current admin and awaiting acceptance.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PendingAdmin {
+    pub new_admin: Address,
+    /// Last ledger sequence at which `accept_admin` succeeds.
+    pub expires_at: u32,
+}
+
+/// A processed deduct, kept for the request-id retention window; see `refund`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeductRecord {
+    pub amount: i128,
+    /// Platform fee taken from `amount`; it went to the treasury and is never refunded.
+    pub fee: i128,
+    /// Sub-account that was charged and is credited on refund.
+    pub depositor: Address,
+    /// Cumulative amount refunded so far.
+    pub refunded: i128,
+    /// Ledger sequence at which the deduct was made; starts the dispute window.
+    pub deducted_at: u32,
+}
+
+/// Owner dispute against a recorded deduct, open until `resolve_dispute`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dispute {
+    /// Sub-account credited with any refund on resolution.
+    pub depositor: Address,
+    /// Amount frozen: the deduct net of platform fee, less refunds, when the dispute opened.
+    pub amount: i128,
+    /// Owner-supplied reason; opaque to the contract.
+    pub reason_code: u32,
+    pub opened_at: u32,
+}
+
+/// Funds reserved by a deducter ahead of a long-running call; see `hold`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hold {
+    /// Deducter that placed the hold and must sign `capture`/`release`.
+    pub caller: Address,
+    pub amount: i128,
+    /// Last ledger sequence at which the hold can be captured.
+    pub expires_at: u32,
+}
+
+/// Vault parameters set at init or by the admin/owner, as returned by `get_config`.
+/// The optional spending cap and platform fee are not included (a contract type cannot hold
+/// an optional struct); read them with `get_spend_cap` and `get_platform_fee`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct VaultConfig {
+    pub owner: Address,
+    pub admin: Address,
+    pub usdc_token: Address,
+    pub revenue_pool: Option<Address>,
+    pub guardian: Option<Address>,
     pub min_deposit: i128,
+    pub max_deduct: i128,
+    pub request_ttl: u32,
+    pub ttl_config: TtlConfig,
+    pub dispute_window: u32,
+}
+
+/// Platform cut taken from every deduct and sent to `treasury`; the rest is settled as
+/// revenue. The fee is `amount * fee_bps / 10_000`, rounded down.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlatformFee {
+    pub fee_bps: u32,
+    pub treasury: Address,
+}
+
+/// Rent-extension policy: once an entry's TTL drops below `threshold` ledgers it is
+/// extended to `extend_to` ledgers. Applied to instance storage on every state change and
+/// to persistent entries (sub-accounts, deducters) when they are touched.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TtlConfig {
+    pub threshold: u32,
+    pub extend_to: u32,
+}
+
+/// Length of a spending-cap window, measured in ledger timestamp seconds.
+/// Windows are fixed, not rolling: each starts at a multiple of the period since
+/// the Unix epoch, so up to twice the limit can be charged across a window boundary.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SpendPeriod {
+    Day,
+    Week,
+    /// 30 days; not a calendar month.
+    Days30,
+}
+
+impl SpendPeriod {
+    /// Window length in seconds.
+    pub fn seconds(&self) -> u64 {
+        match self {
+            SpendPeriod::Day => 86_400,
+            SpendPeriod::Week => 7 * 86_400,
+            SpendPeriod::Days30 => 30 * 86_400,
+        }
+    }
+}
+
+/// Owner-configured budget: at most `limit` may be charged per fixed `period` window.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpendCap {
+    pub period: SpendPeriod,
+    pub limit: i128,
+}
+
+/// Spending in the current window, as reported by `get_spend_window`.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpendWindow {
+    pub period: SpendPeriod,
+    pub limit: i128,
+    /// Ledger timestamp at which the current fixed window started (a multiple of the period).
+    pub window_start: u64,
+    pub spent: i128,
+    pub remaining: i128,
+}
+
+/// Per-operation pause flags. `true` means the operation is frozen.
+///
+/// Passed to `pause`/`unpause` as the set of operations to change and returned by
+/// `get_pause_state`. Owner withdrawals stay available unless `withdraw` is set,
+/// which only the admin (not the guardian) may do.
+#[contracttype]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PauseState {
+    pub deposit: bool,
+    pub deduct: bool,
+    pub batch_deduct: bool,
+    pub distribute: bool,
+    /// Covers both `withdraw` and `withdraw_to`.
+    pub withdraw: bool,
 }
 
 const META_KEY: &str = "meta";
@@ -24,10 +250,64 @@ const USDC_KEY: &str = "usdc";
 const ADMIN_KEY: &str = "admin";
 const REVENUE_POOL_KEY: &str = "revenue_pool";
 const MAX_DEDUCT_KEY: &str = "max_deduct";
+/// Persistent key prefix; `(DEDUCTER_KEY, address)` marks an authorized deducter.
+const DEDUCTER_KEY: &str = "deducter";
+/// Temporary key prefix; `(REQUEST_KEY, request_id)` holds the `DeductRecord` of a processed request.
+const REQUEST_KEY: &str = "request";
+const REQUEST_TTL_KEY: &str = "request_ttl";
+const PAUSE_KEY: &str = "pause";
+const GUARDIAN_KEY: &str = "guardian";
+const PENDING_OWNER_KEY: &str = "pending_owner";
+const PENDING_ADMIN_KEY: &str = "pending_admin";
+const ACCRUED_REVENUE_KEY: &str = "accrued_revenue";
+/// Persistent key prefix; `(DEPOSITOR_KEY, address)` holds that depositor's sub-account balance.
+const DEPOSITOR_KEY: &str = "depositor";
+/// Persistent key prefix; `(HOLDS_KEY, hold_id)` holds an open `Hold`.
+const HOLDS_KEY: &str = "holds";
+/// Persistent key prefix; `(HELD_KEY, address)` holds `(amount, expires_at)` for each of
+/// that depositor's open holds (see `held_amount`).
+const HELD_KEY: &str = "held";
+const SPEND_CAP_KEY: &str = "spend_cap";
+/// Instance key; `(window_start, spent)` for the current spending-cap window.
+const SPENT_KEY: &str = "spent";
+const SCHEMA_VERSION_KEY: &str = "schema_version";
+const TTL_CONFIG_KEY: &str = "ttl_config";
+const DISPUTE_WINDOW_KEY: &str = "dispute_window";
+/// Instance key; accrued revenue frozen by open disputes (vaults without a revenue pool).
+const FROZEN_REVENUE_KEY: &str = "frozen_revenue";
+/// Persistent key prefix; `(DISPUTE_KEY, request_id)` holds an open `Dispute`.
+const DISPUTE_KEY: &str = "dispute";
+const PLATFORM_FEE_KEY: &str = "platform_fee";
+const FEES_COLLECTED_KEY: &str = "fees_collected";
 
 /// Default maximum single deduct amount when not set at init (no cap).
 pub const DEFAULT_MAX_DEDUCT: i128 = i128::MAX;
 
+/// Storage layout version written by this release; see `migrate`.
+/// Version 1 is the layout before per-depositor sub-accounts.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Default TTL threshold, in ledgers, below which storage is extended (~30 days at 5s per ledger).
+pub const DEFAULT_TTL_THRESHOLD: u32 = 518_400;
+
+/// Default TTL, in ledgers, that storage is extended to (~60 days at 5s per ledger).
+pub const DEFAULT_TTL_EXTEND_TO: u32 = 1_036_800;
+
+/// Default dispute window, in ledgers, after a deduct (~7 days at 5s per ledger).
+pub const DEFAULT_DISPUTE_WINDOW: u32 = 120_960;
+
+/// Default retention, in ledgers, for processed request ids (~30 days at 5s per ledger).
+pub const DEFAULT_REQUEST_TTL: u32 = 518_400;
+
+/// Maximum number of payees in a single `batch_distribute` call.
+pub const MAX_DISTRIBUTE_BATCH: u32 = 100;
+
+/// Maximum number of unexpired holds open against one sub-account.
+pub const MAX_OPEN_HOLDS: u32 = 32;
+
+/// Basis-point denominator for `PlatformFee::fee_bps` and `resolve_dispute` splits.
+pub const BPS_DENOMINATOR: u32 = 10_000;
+
 #[contracttype]
 #[derive(Clone, Debug, PartialEq)]
 pub struct DistributeEvent {
@@ -35,18 +315,41 @@ pub struct DistributeEvent {
     pub amount: i128,
 }
 
+/// Subset of the revenue pool interface the vault calls after settling a deduct.
+#[contractclient(name = "RevenuePoolClient")]
+pub trait RevenuePoolInterface {
+    /// Record that `amount` USDC was transferred to the pool by `caller` (the vault).
+    fn receive_payment(env: Env, caller: Address, amount: i128, from_vault: bool);
+
+    /// Send `amount` of USDC previously settled by `caller` (the vault) back to it.
+    fn refund_payment(env: Env, caller: Address, amount: i128);
+
+    /// Hold `amount` of `caller`'s settled USDC back from distribution during a dispute.
+    fn freeze_payment(env: Env, caller: Address, amount: i128);
+
+    /// Release USDC previously frozen with `freeze_payment`.
+    fn unfreeze_payment(env: Env, caller: Address, amount: i128);
+}
+
 #[contract]
 pub struct CalloraVault;
 
 #[contractimpl]
 impl CalloraVault {
     /// Initialize vault for an owner with optional initial balance and minimum deposit.
+    /// The initial balance is credited to the owner's sub-account.
     /// If initial_balance > 0, the contract must already hold at least that much USDC (e.g. deployer transferred in first).
     /// Emits an "init" event with the owner address and initial balance.
     ///
     /// # Arguments
     /// * `revenue_pool` – Optional address to receive USDC on each deduct (e.g. settlement contract). If None, USDC stays in vault.
     /// * `max_deduct` – Optional cap per single deduct; if None, uses DEFAULT_MAX_DEDUCT (no cap).
+    ///
+    /// # Errors
+    /// * `AlreadyInitialized` – init was already called.
+    /// * `InsufficientUsdc`   – the contract holds less USDC than `initial_balance`.
+    /// * `InvalidConfig`      – `max_deduct` is zero or negative, `min_deposit` is negative,
+    ///   or `min_deposit` exceeds `max_deduct`.
     pub fn init(
         env: Env,
         owner: Address,
@@ -55,24 +358,23 @@ impl CalloraVault {
         min_deposit: Option<i128>,
         revenue_pool: Option<Address>,
         max_deduct: Option<i128>,
-    ) -> VaultMeta {
+    ) -> Result<VaultMeta, VaultError> {
+        Self::extend_instance_ttl(&env);
         owner.require_auth();
         if env.storage().instance().has(&Symbol::new(&env, META_KEY)) {
-            panic!("vault already initialized");
+            return Err(VaultError::AlreadyInitialized);
         }
         let balance = initial_balance.unwrap_or(0);
         if balance > 0 {
             let usdc = token::Client::new(&env, &usdc_token);
             let contract_balance = usdc.balance(&env.current_contract_address());
             if contract_balance < balance {
-                panic!("insufficient USDC in contract for initial_balance");
+                return Err(VaultError::InsufficientUsdc);
             }
         }
         let min_deposit_val = min_deposit.unwrap_or(0);
         let max_deduct_val = max_deduct.unwrap_or(DEFAULT_MAX_DEDUCT);
-        if max_deduct_val <= 0 {
-            panic!("max_deduct must be positive");
-        }
+        Self::validate_limits(min_deposit_val, max_deduct_val)?;
         let meta = VaultMeta {
             owner: owner.clone(),
             balance,
@@ -84,299 +386,1773 @@ impl CalloraVault {
         inst.set(&Symbol::new(&env, META_KEY), &meta);
         inst.set(&Symbol::new(&env, USDC_KEY), &usdc_token);
         inst.set(&Symbol::new(&env, ADMIN_KEY), &owner);
+        inst.set(&Symbol::new(&env, REVENUE_POOL_KEY), &revenue_pool);
+        inst.set(&Symbol::new(&env, MAX_DEDUCT_KEY), &max_deduct_val);
+        inst.set(&Symbol::new(&env, SCHEMA_VERSION_KEY), &SCHEMA_VERSION);
+        Self::set_sub_balance(&env, &owner, balance);
 
         env.events()
             .publish((Symbol::new(&env, "init"), owner), balance);
 
-        meta
+        Ok(meta)
     }
 
     /// Return the current admin address.
-    pub fn get_admin(env: Env) -> Address {
+    pub fn get_admin(env: Env) -> Result<Address, VaultError> {
         env.storage()
             .instance()
             .get(&Symbol::new(&env, ADMIN_KEY))
-            .unwrap_or_else(|| panic!("vault not initialized"))
+            .ok_or(VaultError::NotInitialized)
     }
 
-    /// Replace the current admin. Only the existing admin may call this.
-    pub fn set_admin(env: Env, caller: Address, new_admin: Address) {
-        caller.require_auth();
-        let current_admin = Self::get_admin(env.clone());
-        if caller != current_admin {
-            panic!("unauthorized: caller is not admin");
+    /// Propose `new_admin` as the next admin. Only the current admin may call this.
+    /// The handover completes only when `new_admin` calls `accept_admin` within
+    /// `expires_in_ledgers` ledgers, so a mistyped address never takes control.
+    /// A new proposal replaces any pending one. Emits an "admin_proposed" event.
+    pub fn propose_admin(
+        env: Env,
+        caller: Address,
+        new_admin: Address,
+        expires_in_ledgers: u32,
+    ) -> Result<PendingAdmin, VaultError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_admin(&env, &caller)?;
+        if expires_in_ledgers == 0 {
+            return Err(VaultError::InvalidConfig);
+        }
+        let pending = PendingAdmin {
+            new_admin: new_admin.clone(),
+            expires_at: env.ledger().sequence().saturating_add(expires_in_ledgers),
+        };
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, PENDING_ADMIN_KEY), &pending);
+
+        env.events().publish(
+            (Symbol::new(&env, "admin_proposed"), caller, new_admin),
+            pending.expires_at,
+        );
+        Ok(pending)
+    }
+
+    /// Accept a pending admin handover. Must be signed by the proposed admin before the
+    /// deadline. Emits an "admin_changed" event with the old and new admin.
+    pub fn accept_admin(env: Env) -> Result<Address, VaultError> {
+        Self::extend_instance_ttl(&env);
+        let pending = Self::get_pending_admin(env.clone()).ok_or(VaultError::NoPendingTransfer)?;
+        pending.new_admin.require_auth();
+        if env.ledger().sequence() > pending.expires_at {
+            return Err(VaultError::TransferExpired);
         }
+
+        let old_admin = Self::get_admin(env.clone())?;
         let inst = env.storage().instance();
-        inst.set(&Symbol::new(&env, ADMIN_KEY), &new_admin);
+        inst.set(&Symbol::new(&env, ADMIN_KEY), &pending.new_admin);
+        inst.remove(&Symbol::new(&env, PENDING_ADMIN_KEY));
+
+        env.events().publish(
+            (
+                Symbol::new(&env, "admin_changed"),
+                old_admin,
+                pending.new_admin.clone(),
+            ),
+            (),
+        );
+        Ok(pending.new_admin)
     }
 
-    /// Return the maximum allowed amount for a single deduct (configurable at init).
-    pub fn get_max_deduct(env: Env) -> i128 {
+    /// Withdraw a pending admin proposal. Only the current admin may call this.
+    /// Emits an "admin_cancelled" event with the admin and the withdrawn candidate.
+    pub fn cancel_admin_transfer(env: Env, caller: Address) -> Result<(), VaultError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_admin(&env, &caller)?;
+        let pending = Self::get_pending_admin(env.clone()).ok_or(VaultError::NoPendingTransfer)?;
         env.storage()
             .instance()
-            .get(&Symbol::new(&env, MAX_DEDUCT_KEY))
-            .unwrap_or_else(|| panic!("vault not initialized"))
+            .remove(&Symbol::new(&env, PENDING_ADMIN_KEY));
+
+        env.events().publish(
+            (
+                Symbol::new(&env, "admin_cancelled"),
+                caller,
+                pending.new_admin,
+            ),
+            (),
+        );
+        Ok(())
     }
 
-    /// Return the revenue pool address if set (receives USDC on deduct).
-    pub fn get_revenue_pool(env: Env) -> Option<Address> {
+    /// Return the pending admin handover, if any.
+    pub fn get_pending_admin(env: Env) -> Option<PendingAdmin> {
         env.storage()
             .instance()
-            .get(&Symbol::new(&env, REVENUE_POOL_KEY))
-            .unwrap_or(None)
+            .get(&Symbol::new(&env, PENDING_ADMIN_KEY))
     }
 
-    /// Distribute accumulated USDC to a single developer address.
-    ///
-    /// # Access control
-    /// Only the admin (backend / multisig) may call this.
-    ///
-    /// # Arguments
-    /// * `caller` – Must be the current admin address.
-    /// * `to`     – Developer wallet to receive the USDC.
-    /// * `amount` – Amount in USDC micro-units (must be > 0 and ≤ vault balance).
-    ///
-    /// # Panics
-    /// * `"unauthorized: caller is not admin"` – caller is not the admin.
-    /// * `"amount must be positive"`           – amount is zero or negative.
-    /// * `"insufficient USDC balance"`         – vault holds less than amount.
-    ///
-    /// # Events
-    /// Emits topic `("distribute", to)` with data `amount` on success.
-    pub fn distribute(env: Env, caller: Address, to: Address, amount: i128) {
-        // 1. Require on-chain signature from caller.
-        caller.require_auth();
+    /// Propose `new_owner` as the next vault owner. Owner only.
+    /// The transfer completes when `new_owner` calls `accept_ownership`, optionally within
+    /// `expires_in_ledgers` ledgers. A new proposal replaces any pending one.
+    /// Emits an "ownership_proposed" event with the current and proposed owner.
+    pub fn propose_owner(
+        env: Env,
+        new_owner: Address,
+        expires_in_ledgers: Option<u32>,
+    ) -> Result<PendingOwner, VaultError> {
+        Self::extend_instance_ttl(&env);
+        let meta = Self::get_meta(env.clone())?;
+        meta.owner.require_auth();
+        let pending = PendingOwner {
+            new_owner: new_owner.clone(),
+            expires_at: expires_in_ledgers.map(|n| env.ledger().sequence().saturating_add(n)),
+        };
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, PENDING_OWNER_KEY), &pending);
 
-        // 2. Only the registered admin may distribute.
-        let admin = Self::get_admin(env.clone());
-        if caller != admin {
-            panic!("unauthorized: caller is not admin");
-        }
+        env.events().publish(
+            (
+                Symbol::new(&env, "ownership_proposed"),
+                meta.owner,
+                new_owner,
+            ),
+            pending.expires_at,
+        );
+        Ok(pending)
+    }
 
-        // 3. Amount must be positive.
-        if amount <= 0 {
-            panic!("amount must be positive");
+    /// Accept a pending ownership transfer. Must be signed by the proposed owner before the
+    /// proposal expires. The old owner's sub-account and open holds move to the new owner.
+    /// Emits an "ownership_transfer" event with the old and new owner.
+    pub fn accept_ownership(env: Env) -> Result<VaultMeta, VaultError> {
+        Self::extend_instance_ttl(&env);
+        let pending = Self::get_pending_owner(env.clone()).ok_or(VaultError::NoPendingTransfer)?;
+        pending.new_owner.require_auth();
+        if let Some(expires_at) = pending.expires_at {
+            if env.ledger().sequence() > expires_at {
+                return Err(VaultError::TransferExpired);
+            }
         }
 
-        // 4. Load the USDC token address.
-        let usdc_opt: Option<Address> = env.storage().instance().get(&Symbol::new(&env, USDC_KEY));
-        let usdc_address: Address = usdc_opt.unwrap_or_else(|| panic!("vault not initialized"));
+        let mut meta = Self::get_meta(env.clone())?;
+        let old_owner = meta.owner.clone();
+        meta.owner = pending.new_owner.clone();
+        let moved = Self::balance_of(env.clone(), old_owner.clone());
+        Self::set_sub_balance(&env, &old_owner, 0);
+        let carried = Self::balance_of(env.clone(), meta.owner.clone()) + moved;
+        Self::set_sub_balance(&env, &meta.owner, carried);
+        let held = Self::get_held(&env, &old_owner);
+        env.storage()
+            .persistent()
+            .remove(&(Symbol::new(&env, HELD_KEY), old_owner.clone()));
+        Self::set_held(&env, &meta.owner, held);
+        let inst = env.storage().instance();
+        inst.set(&Symbol::new(&env, "meta"), &meta);
+        inst.remove(&Symbol::new(&env, PENDING_OWNER_KEY));
 
-        let usdc = token::Client::new(&env, &usdc_address);
+        env.events().publish(
+            (
+                Symbol::new(&env, "ownership_transfer"),
+                old_owner,
+                pending.new_owner,
+            ),
+            (),
+        );
+        Ok(meta)
+    }
 
-        // 5. Check vault has enough USDC.
-        let vault_balance = usdc.balance(&env.current_contract_address());
-        if vault_balance < amount {
-            panic!("insufficient USDC balance");
-        }
+    /// Withdraw a pending ownership proposal. Owner only.
+    /// Emits an "ownership_cancelled" event with the owner and the withdrawn candidate.
+    pub fn cancel_ownership_transfer(env: Env) -> Result<(), VaultError> {
+        Self::extend_instance_ttl(&env);
+        let meta = Self::get_meta(env.clone())?;
+        meta.owner.require_auth();
+        let pending = Self::get_pending_owner(env.clone()).ok_or(VaultError::NoPendingTransfer)?;
+        env.storage()
+            .instance()
+            .remove(&Symbol::new(&env, PENDING_OWNER_KEY));
 
-        // 6. Transfer USDC from vault to developer.
-        usdc.transfer(&env.current_contract_address(), &to, &amount);
+        env.events().publish(
+            (
+                Symbol::new(&env, "ownership_cancelled"),
+                meta.owner,
+                pending.new_owner,
+            ),
+            (),
+        );
+        Ok(())
+    }
 
-        // 7. Emit distribute event.
-        env.events()
-            .publish((Symbol::new(&env, "distribute"), to), amount);
+    /// Return the pending ownership transfer, if any.
+    pub fn get_pending_owner(env: Env) -> Option<PendingOwner> {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, PENDING_OWNER_KEY))
     }
 
-    /// Get vault metadata (owner and balance).
-    pub fn get_meta(env: Env) -> VaultMeta {
+    /// Set or clear the guardian: an address that may `pause` deposit, deduct, batch_deduct
+    /// and distribute, but can neither unpause nor freeze withdrawals. Admin only.
+    pub fn set_guardian(
+        env: Env,
+        caller: Address,
+        guardian: Option<Address>,
+    ) -> Result<(), VaultError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_admin(&env, &caller)?;
         env.storage()
             .instance()
-            .get(&Symbol::new(&env, META_KEY))
-            .unwrap_or_else(|| panic!("vault not initialized"))
+            .set(&Symbol::new(&env, GUARDIAN_KEY), &guardian);
+        Ok(())
     }
 
-    /// Deposit: user transfers USDC to the contract; contract increases internal balance.
-    /// Caller must have authorized the transfer (token transfer_from). Supports multiple depositors.
-    /// Emits a "deposit" event with the depositor address and amount.
-    pub fn deposit(env: Env, from: Address, amount: i128) -> i128 {
-        from.require_auth();
+    /// Return the guardian address, if one is set.
+    pub fn get_guardian(env: Env) -> Option<Address> {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, GUARDIAN_KEY))
+            .unwrap_or(None)
+    }
 
-        let mut meta = Self::get_meta(env.clone());
-        assert!(
-            amount >= meta.min_deposit,
-            "deposit below minimum: {} < {}",
-            amount,
-            meta.min_deposit
-        );
+    /// Freeze every operation flagged in `ops`; flags left `false` are unchanged.
+    /// Callable by the admin, or by the guardian unless `ops.withdraw` is set.
+    /// Emits a "pause" event with the caller and the requested operation set.
+    pub fn pause(env: Env, caller: Address, ops: PauseState) -> Result<PauseState, VaultError> {
+        Self::extend_instance_ttl(&env);
+        caller.require_auth();
+        let admin = Self::get_admin(env.clone())?;
+        if caller != admin {
+            // The guardian may freeze anything except owner withdrawals.
+            let is_guardian = Self::get_guardian(env.clone()) == Some(caller.clone());
+            if !is_guardian || ops.withdraw {
+                return Err(VaultError::Unauthorized);
+            }
+        }
 
-        let usdc_address: Address = env
-            .storage()
+        let mut state = Self::get_pause_state(env.clone());
+        state.deposit |= ops.deposit;
+        state.deduct |= ops.deduct;
+        state.batch_deduct |= ops.batch_deduct;
+        state.distribute |= ops.distribute;
+        state.withdraw |= ops.withdraw;
+        env.storage()
             .instance()
-            .get(&Symbol::new(&env, USDC_KEY))
-            .unwrap_or_else(|| panic!("vault not initialized"));
-        let usdc = token::Client::new(&env, &usdc_address);
-        usdc.transfer_from(
-            &env.current_contract_address(),
-            &from,
-            &env.current_contract_address(),
-            &amount,
-        );
-
-        meta.balance += amount;
-        let inst = env.storage().instance();
-        inst.set(&Symbol::new(&env, "meta"), &meta);
+            .set(&Symbol::new(&env, PAUSE_KEY), &state);
 
         env.events()
-            .publish((Symbol::new(&env, "deposit"), from), amount);
-
-        meta.balance
+            .publish((Symbol::new(&env, "pause"), caller), ops);
+        Ok(state)
     }
 
-    /// Deduct balance for an API call. Callable by authorized caller (e.g. backend).
-    /// Amount must not exceed max single deduct (see init / get_max_deduct).
-    /// If revenue pool is set, USDC is transferred to it; otherwise it remains in the vault.
-    /// Emits a "deduct" event with caller, optional request_id, amount, and new balance.
-    pub fn deduct(env: Env, caller: Address, amount: i128, request_id: Option<Symbol>) -> i128 {
-        caller.require_auth();
-        let max_deduct = Self::get_max_deduct(env.clone());
-        assert!(amount > 0, "amount must be positive");
-        assert!(amount <= max_deduct, "deduct amount exceeds max_deduct");
-
-        let mut meta = Self::get_meta(env.clone());
-        assert!(meta.balance >= amount, "insufficient balance");
+    /// Resume every operation flagged in `ops`; flags left `false` are unchanged. Admin only.
+    /// Emits an "unpause" event with the caller and the requested operation set.
+    pub fn unpause(env: Env, caller: Address, ops: PauseState) -> Result<PauseState, VaultError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_admin(&env, &caller)?;
 
-        let usdc_address: Address = env
-            .storage()
-            .instance()
-            .get(&Symbol::new(&env, USDC_KEY))
-            .unwrap_or_else(|| panic!("vault not initialized"));
-        let revenue_pool: Option<Address> = env
-            .storage()
+        let mut state = Self::get_pause_state(env.clone());
+        state.deposit &= !ops.deposit;
+        state.deduct &= !ops.deduct;
+        state.batch_deduct &= !ops.batch_deduct;
+        state.distribute &= !ops.distribute;
+        state.withdraw &= !ops.withdraw;
+        env.storage()
             .instance()
-            .get(&Symbol::new(&env, REVENUE_POOL_KEY))
-            .unwrap_or(None);
+            .set(&Symbol::new(&env, PAUSE_KEY), &state);
 
-        meta.balance -= amount;
-        let inst = env.storage().instance();
-        inst.set(&Symbol::new(&env, "meta"), &meta);
+        env.events()
+            .publish((Symbol::new(&env, "unpause"), caller), ops);
+        Ok(state)
+    }
 
-        let topics = match &request_id {
-            Some(rid) => (Symbol::new(&env, "deduct"), caller.clone(), rid.clone()),
-            None => (
-                Symbol::new(&env, "deduct"),
-                caller.clone(),
-                Symbol::new(&env, ""),
-            ),
-        };
-        env.events().publish(topics, (amount, meta.balance));
-        meta.balance
+    /// Return which operations are currently paused.
+    pub fn get_pause_state(env: Env) -> PauseState {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, PAUSE_KEY))
+            .unwrap_or_default()
     }
 
-    /// Batch deduct: multiple (amount, optional request_id) in one transaction.
-    /// Each amount must not exceed max_deduct. Reverts entire batch if any check fails.
-    /// If revenue pool is set, total deducted USDC is transferred to it once.
-    /// Emits one "deduct" event per item.
-    pub fn batch_deduct(env: Env, caller: Address, items: Vec<DeductItem>) -> i128 {
-        caller.require_auth();
-        let max_deduct = Self::get_max_deduct(env.clone());
-        let mut meta = Self::get_meta(env.clone());
-        let n = items.len();
-        assert!(n > 0, "batch_deduct requires at least one item");
+    /// Authorize `deducter` (e.g. a backend operator key) to call `deduct` and `batch_deduct`.
+    /// Only the admin may call this. Emits a "deducter_added" event.
+    pub fn add_deducter(env: Env, caller: Address, deducter: Address) -> Result<(), VaultError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_admin(&env, &caller)?;
+        let key = (Symbol::new(&env, DEDUCTER_KEY), deducter.clone());
+        env.storage().persistent().set(&key, &true);
+        Self::extend_persistent_ttl(&env, &key);
 
-        let mut total_deduct = 0i128;
-        let mut running = meta.balance;
-        for item in items.iter() {
-            assert!(item.amount > 0, "amount must be positive");
-            assert!(
-                item.amount <= max_deduct,
-                "deduct amount exceeds max_deduct"
-            );
-            assert!(running >= item.amount, "insufficient balance");
-            running -= item.amount;
-            total_deduct += item.amount;
+        env.events()
+            .publish((Symbol::new(&env, "deducter_added"), deducter), caller);
+        Ok(())
+    }
+
+    /// Revoke a previously authorized deducter. Only the admin may call this.
+    /// Fails with `DeducterNotFound` if the address is not on the allowlist.
+    /// Emits a "deducter_removed" event.
+    pub fn remove_deducter(env: Env, caller: Address, deducter: Address) -> Result<(), VaultError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_admin(&env, &caller)?;
+        let key = (Symbol::new(&env, DEDUCTER_KEY), deducter.clone());
+        if !env.storage().persistent().has(&key) {
+            return Err(VaultError::DeducterNotFound);
         }
+        env.storage().persistent().remove(&key);
 
-        let usdc_address: Address = env
-            .storage()
-            .instance()
-            .get(&Symbol::new(&env, USDC_KEY))
-            .unwrap_or_else(|| panic!("vault not initialized"));
-        let revenue_pool: Option<Address> = env
-            .storage()
-            .instance()
-            .get(&Symbol::new(&env, REVENUE_POOL_KEY))
-            .unwrap_or(None);
+        env.events()
+            .publish((Symbol::new(&env, "deducter_removed"), deducter), caller);
+        Ok(())
+    }
 
-        let mut balance = meta.balance;
-        for item in items.iter() {
-            balance -= item.amount;
-            let topics = match &item.request_id {
-                Some(rid) => (Symbol::new(&env, "deduct"), caller.clone(), rid.clone()),
-                None => (
-                    Symbol::new(&env, "deduct"),
-                    caller.clone(),
-                    Symbol::new(&env, ""),
-                ),
-            };
-            env.events().publish(topics, (item.amount, balance));
-        }
+    /// Return true if `deducter` is on the authorized deducter allowlist.
+    pub fn is_deducter(env: Env, deducter: Address) -> bool {
+        env.storage()
+            .persistent()
+            .has(&(Symbol::new(&env, DEDUCTER_KEY), deducter))
+    }
 
-        meta.balance = balance;
-        let inst = env.storage().instance();
-        inst.set(&Symbol::new(&env, "meta"), &meta);
-        meta.balance
+    /// Return true if a deduct with `request_id` was processed within the retention window.
+    /// Backends can use this to decide whether a timed-out deduct is safe to retry.
+    pub fn is_processed(env: Env, request_id: Symbol) -> bool {
+        env.storage()
+            .temporary()
+            .has(&(Symbol::new(&env, REQUEST_KEY), request_id))
     }
 
-    /// Withdraw from vault. Callable only by the vault owner; reduces balance and transfers USDC to owner.
-    pub fn withdraw(env: Env, amount: i128) -> i128 {
-        let mut meta = Self::get_meta(env.clone());
-        meta.owner.require_auth();
-        assert!(amount > 0, "amount must be positive");
-        assert!(meta.balance >= amount, "insufficient balance");
+    /// Return how many ledgers processed request ids are retained for duplicate detection.
+    pub fn get_request_ttl(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, REQUEST_TTL_KEY))
+            .unwrap_or(DEFAULT_REQUEST_TTL)
+    }
 
-        let usdc_address: Address = env
-            .storage()
+    /// Set the retention window (in ledgers) for processed request ids. Admin only.
+    /// Applies to request ids recorded after the change. Fails with `InvalidConfig` if zero or
+    /// above the network's max TTL, which temporary entries cannot be extended past.
+    pub fn set_request_ttl(env: Env, caller: Address, ledgers: u32) -> Result<(), VaultError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_admin(&env, &caller)?;
+        if ledgers == 0 || ledgers > env.storage().max_ttl() {
+            return Err(VaultError::InvalidConfig);
+        }
+        env.storage()
             .instance()
-            .get(&Symbol::new(&env, USDC_KEY))
-            .unwrap_or_else(|| panic!("vault not initialized"));
-        let usdc = token::Client::new(&env, &usdc_address);
-        usdc.transfer(&env.current_contract_address(), &meta.owner, &amount);
+            .set(&Symbol::new(&env, REQUEST_TTL_KEY), &ledgers);
+        Ok(())
+    }
 
-        meta.balance -= amount;
-        let inst = env.storage().instance();
-        inst.set(&Symbol::new(&env, "meta"), &meta);
+    /// Change the minimum deposit. Admin only.
+    /// Fails with `InvalidConfig` if negative or above the current `max_deduct`.
+    /// Emits a "config_updated" event with the old and new values.
+    pub fn set_min_deposit(env: Env, caller: Address, amount: i128) -> Result<(), VaultError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_admin(&env, &caller)?;
+        let mut meta = Self::get_meta(env.clone())?;
+        Self::validate_limits(amount, Self::get_max_deduct(env.clone())?)?;
+
+        let old = meta.min_deposit;
+        meta.min_deposit = amount;
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, "meta"), &meta);
 
         env.events().publish(
-            (Symbol::new(&env, "withdraw"), meta.owner.clone()),
-            (amount, meta.balance),
+            (
+                Symbol::new(&env, "config_updated"),
+                Symbol::new(&env, "min_deposit"),
+            ),
+            (old, amount),
         );
-        meta.balance
+        Ok(())
     }
 
-    /// Withdraw from vault to a designated address. Owner-only; transfers USDC to `to`.
-    pub fn withdraw_to(env: Env, to: Address, amount: i128) -> i128 {
-        let mut meta = Self::get_meta(env.clone());
-        meta.owner.require_auth();
-        assert!(amount > 0, "amount must be positive");
-        assert!(meta.balance >= amount, "insufficient balance");
+    /// Change the maximum single deduct. Admin only.
+    /// Fails with `InvalidConfig` if not positive or below the current `min_deposit`.
+    /// Emits a "config_updated" event with the old and new values.
+    pub fn set_max_deduct(env: Env, caller: Address, amount: i128) -> Result<(), VaultError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_admin(&env, &caller)?;
+        let meta = Self::get_meta(env.clone())?;
+        Self::validate_limits(meta.min_deposit, amount)?;
 
-        let usdc_address: Address = env
-            .storage()
+        let old = Self::get_max_deduct(env.clone())?;
+        env.storage()
             .instance()
-            .get(&Symbol::new(&env, USDC_KEY))
-            .unwrap_or_else(|| panic!("vault not initialized"));
-        let usdc = token::Client::new(&env, &usdc_address);
-        usdc.transfer(&env.current_contract_address(), &to, &amount);
-
-        meta.balance -= amount;
-        let inst = env.storage().instance();
-        inst.set(&Symbol::new(&env, "meta"), &meta);
+            .set(&Symbol::new(&env, MAX_DEDUCT_KEY), &amount);
 
         env.events().publish(
             (
-                Symbol::new(&env, "withdraw_to"),
-                meta.owner.clone(),
-                to.clone(),
+                Symbol::new(&env, "config_updated"),
+                Symbol::new(&env, "max_deduct"),
             ),
-            (amount, meta.balance),
+            (old, amount),
         );
-        meta.balance
+        Ok(())
     }
 
-    /// Return current balance.
-    pub fn balance(env: Env) -> i128 {
-        Self::get_meta(env).balance
+    /// Return the vault's parameters in one struct (see `VaultConfig` for what is omitted).
+    pub fn get_config(env: Env) -> Result<VaultConfig, VaultError> {
+        let meta = Self::get_meta(env.clone())?;
+        Ok(VaultConfig {
+            owner: meta.owner,
+            admin: Self::get_admin(env.clone())?,
+            usdc_token: Self::get_usdc(&env)?,
+            revenue_pool: Self::get_revenue_pool(env.clone()),
+            guardian: Self::get_guardian(env.clone()),
+            min_deposit: meta.min_deposit,
+            max_deduct: Self::get_max_deduct(env.clone())?,
+            request_ttl: Self::get_request_ttl(env.clone()),
+            ttl_config: Self::get_ttl_config(env.clone()),
+            dispute_window: Self::get_dispute_window(env),
+        })
+    }
+
+    /// Set or clear (`None`) the platform fee taken from every deduct. Admin only.
+    /// Fails with `InvalidConfig` if `fee_bps` exceeds 10_000 (100%).
+    /// Emits a "config_updated" event with the old and new fee.
+    pub fn set_platform_fee(
+        env: Env,
+        caller: Address,
+        fee: Option<PlatformFee>,
+    ) -> Result<(), VaultError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_admin(&env, &caller)?;
+        let old = Self::get_platform_fee(env.clone());
+        let inst = env.storage().instance();
+        match &fee {
+            Some(f) => {
+                if f.fee_bps > BPS_DENOMINATOR {
+                    return Err(VaultError::InvalidConfig);
+                }
+                inst.set(&Symbol::new(&env, PLATFORM_FEE_KEY), f);
+            }
+            None => inst.remove(&Symbol::new(&env, PLATFORM_FEE_KEY)),
+        }
+
+        env.events().publish(
+            (
+                Symbol::new(&env, "config_updated"),
+                Symbol::new(&env, "platform_fee"),
+            ),
+            (old, fee),
+        );
+        Ok(())
+    }
+
+    /// Return the configured platform fee, if any.
+    pub fn get_platform_fee(env: Env) -> Option<PlatformFee> {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, PLATFORM_FEE_KEY))
+    }
+
+    /// Return the lifetime total of platform fees sent to the treasury.
+    pub fn get_fees_collected(env: Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, FEES_COLLECTED_KEY))
+            .unwrap_or(0)
+    }
+
+    /// Replace this vault's code with the already-uploaded WASM `new_wasm_hash`, keeping the
+    /// contract address and storage. Admin only. Call `migrate` afterwards when the new
+    /// release bumps `SCHEMA_VERSION`; until then every balance-changing call fails with
+    /// `MigrationPending`. Emits an "upgraded" event with the new WASM hash.
+    pub fn upgrade(env: Env, caller: Address, new_wasm_hash: BytesN<32>) -> Result<(), VaultError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_admin(&env, &caller)?;
+        env.deployer()
+            .update_current_contract_wasm(new_wasm_hash.clone());
+
+        env.events()
+            .publish((Symbol::new(&env, "upgraded"), caller), new_wasm_hash);
+        Ok(())
+    }
+
+    /// Return the storage layout version of this instance.
+    /// Vaults initialized before versioning was introduced report 1.
+    pub fn get_schema_version(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, SCHEMA_VERSION_KEY))
+            .unwrap_or(1)
+    }
+
+    /// Convert storage written by an older release to the current `SCHEMA_VERSION` layout.
+    /// Admin only; a no-op when the instance is already current. Returns the resulting version.
+    /// Emits a "migrated" event with the old and new versions.
+    ///
+    /// Steps:
+    /// * 1 → 2: `VaultMeta.balance` predates sub-accounts, so it is credited to the owner's
+    ///   sub-account (see `balance_of`). `max_deduct` (default `DEFAULT_MAX_DEDUCT`) and
+    ///   `revenue_pool` (none) are seeded if missing, and USDC held beyond `VaultMeta.balance`
+    ///   is recorded as accrued revenue so it stays distributable.
+    pub fn migrate(env: Env, caller: Address) -> Result<u32, VaultError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_admin(&env, &caller)?;
+        let from = Self::get_schema_version(env.clone());
+        if from >= SCHEMA_VERSION {
+            return Ok(from);
+        }
+
+        if from < 2 {
+            let meta = Self::get_meta(env.clone())?;
+            Self::set_sub_balance(&env, &meta.owner, meta.balance);
+            let inst = env.storage().instance();
+            let max_deduct_key = Symbol::new(&env, MAX_DEDUCT_KEY);
+            if !inst.has(&max_deduct_key) {
+                inst.set(&max_deduct_key, &DEFAULT_MAX_DEDUCT);
+            }
+            let revenue_pool_key = Symbol::new(&env, REVENUE_POOL_KEY);
+            if !inst.has(&revenue_pool_key) {
+                inst.set(&revenue_pool_key, &None::<Address>);
+            }
+            let accrued_key = Symbol::new(&env, ACCRUED_REVENUE_KEY);
+            if !inst.has(&accrued_key) {
+                let usdc = token::Client::new(&env, &Self::get_usdc(&env)?);
+                let surplus = usdc.balance(&env.current_contract_address()) - meta.balance;
+                inst.set(&accrued_key, &surplus.max(0));
+            }
+        }
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, SCHEMA_VERSION_KEY), &SCHEMA_VERSION);
+
+        env.events().publish(
+            (Symbol::new(&env, "migrated"), caller),
+            (from, SCHEMA_VERSION),
+        );
+        Ok(SCHEMA_VERSION)
+    }
+
+    /// Return the rent-extension policy (defaults to `DEFAULT_TTL_THRESHOLD`/`DEFAULT_TTL_EXTEND_TO`).
+    pub fn get_ttl_config(env: Env) -> TtlConfig {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, TTL_CONFIG_KEY))
+            .unwrap_or(TtlConfig {
+                threshold: DEFAULT_TTL_THRESHOLD,
+                extend_to: DEFAULT_TTL_EXTEND_TO,
+            })
+    }
+
+    /// Change the rent-extension policy. Admin only.
+    /// Fails with `InvalidConfig` unless `0 < threshold < extend_to <= ` the network's max TTL.
+    pub fn set_ttl_config(env: Env, caller: Address, config: TtlConfig) -> Result<(), VaultError> {
+        Self::require_admin(&env, &caller)?;
+        if config.threshold == 0
+            || config.threshold >= config.extend_to
+            || config.extend_to > env.storage().max_ttl()
+        {
+            return Err(VaultError::InvalidConfig);
+        }
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, TTL_CONFIG_KEY), &config);
+        Self::extend_instance_ttl(&env);
+        Ok(())
+    }
+
+    /// Extend the vault's instance TTL per `get_ttl_config`. Callable by anyone willing to pay
+    /// the rent, so inactive vaults need not be archived.
+    pub fn extend_ttl(env: Env) {
+        Self::extend_instance_ttl(&env);
+    }
+
+    /// Extend the TTL of `depositor`'s sub-account entry, if it exists. Callable by anyone.
+    pub fn extend_balance_ttl(env: Env, depositor: Address) {
+        Self::extend_instance_ttl(&env);
+        let key = (Symbol::new(&env, DEPOSITOR_KEY), depositor);
+        if env.storage().persistent().has(&key) {
+            Self::extend_persistent_ttl(&env, &key);
+        }
+    }
+
+    /// Return the maximum allowed amount for a single deduct (see `set_max_deduct`).
+    pub fn get_max_deduct(env: Env) -> Result<i128, VaultError> {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, MAX_DEDUCT_KEY))
+            .ok_or(VaultError::NotInitialized)
+    }
+
+    /// Return the revenue pool address if set (receives USDC on deduct).
+    pub fn get_revenue_pool(env: Env) -> Option<Address> {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, REVENUE_POOL_KEY))
+            .unwrap_or(None)
+    }
+
+    /// Return earned revenue held by the vault that has not been distributed yet.
+    ///
+    /// Grows on every deduct settled without a revenue pool and shrinks on `distribute`,
+    /// so the vault's USDC balance always covers `balance()` plus this amount.
+    pub fn get_accrued_revenue(env: Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, ACCRUED_REVENUE_KEY))
+            .unwrap_or(0)
+    }
+
+    /// Distribute earned revenue (USDC from deducts) to a single developer address.
+    /// Customer-prepaid balances are never distributable; see `get_accrued_revenue`.
+    ///
+    /// # Access control
+    /// Only the admin (backend / multisig) may call this.
+    ///
+    /// # Arguments
+    /// * `caller` – Must be the current admin address.
+    /// * `to`     – Developer wallet to receive the USDC.
+    /// * `amount` – Amount in USDC micro-units (must be > 0 and ≤ accrued revenue).
+    ///
+    /// # Errors
+    /// * `Unauthorized`     – caller is not the admin.
+    /// * `Paused`           – distribute is paused.
+    /// * `InvalidAmount`    – amount is zero or negative.
+    /// * `ExceedsAccruedRevenue` – amount exceeds undistributed earned revenue.
+    /// * `InsufficientUsdc` – vault holds less than amount.
+    ///
+    /// # Events
+    /// Emits topic `("distribute", to)` with data `amount` on success.
+    pub fn distribute(
+        env: Env,
+        caller: Address,
+        to: Address,
+        amount: i128,
+    ) -> Result<(), VaultError> {
+        Self::extend_instance_ttl(&env);
+        // 1. Require on-chain signature from caller; only the registered admin may distribute.
+        Self::require_admin(&env, &caller)?;
+
+        // 2. Distribution must not be paused.
+        if Self::get_pause_state(env.clone()).distribute {
+            return Err(VaultError::Paused);
+        }
+        Self::require_current_schema(&env)?;
+
+        // 3. Amount must be positive.
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+
+        // 4. Only earned revenue may leave; customer balances and disputed revenue stay put.
+        let accrued = Self::get_accrued_revenue(env.clone());
+        if amount > accrued - Self::get_frozen_revenue(&env) {
+            return Err(VaultError::ExceedsAccruedRevenue);
+        }
+
+        // 5. Load the USDC token address.
+        let usdc = token::Client::new(&env, &Self::get_usdc(&env)?);
+
+        // 6. Check vault has enough USDC.
+        let vault_balance = usdc.balance(&env.current_contract_address());
+        if vault_balance < amount {
+            return Err(VaultError::InsufficientUsdc);
+        }
+
+        // 7. Transfer USDC from vault to developer and book it against accrued revenue.
+        usdc.transfer(&env.current_contract_address(), &to, &amount);
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, ACCRUED_REVENUE_KEY), &(accrued - amount));
+
+        // 8. Emit distribute event.
+        env.events()
+            .publish((Symbol::new(&env, "distribute"), to), amount);
+        Ok(())
+    }
+
+    /// Distribute earned revenue to many developers in one call, all or nothing.
+    /// Same access, pause and accrued-revenue rules as `distribute`, checked once against
+    /// the total. At most `MAX_DISTRIBUTE_BATCH` payees per call.
+    ///
+    /// # Errors
+    /// * `EmptyBatch`    – `payouts` is empty.
+    /// * `BatchTooLarge` – more than `MAX_DISTRIBUTE_BATCH` payees.
+    /// * `InvalidAmount` – any amount is zero or negative.
+    /// * Otherwise as `distribute`, for the total.
+    ///
+    /// # Events
+    /// Emits `("distribute", to)` per payee, then `("batch_distribute", caller)` with data
+    /// `(payee count, total)`.
+    pub fn batch_distribute(
+        env: Env,
+        caller: Address,
+        payouts: Vec<(Address, i128)>,
+    ) -> Result<i128, VaultError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_admin(&env, &caller)?;
+        if Self::get_pause_state(env.clone()).distribute {
+            return Err(VaultError::Paused);
+        }
+        Self::require_current_schema(&env)?;
+        if payouts.is_empty() {
+            return Err(VaultError::EmptyBatch);
+        }
+        if payouts.len() > MAX_DISTRIBUTE_BATCH {
+            return Err(VaultError::BatchTooLarge);
+        }
+
+        let mut total = 0i128;
+        for (_, amount) in payouts.iter() {
+            if amount <= 0 {
+                return Err(VaultError::InvalidAmount);
+            }
+            total = total.checked_add(amount).ok_or(VaultError::InvalidAmount)?;
+        }
+        let accrued = Self::get_accrued_revenue(env.clone());
+        if total > accrued - Self::get_frozen_revenue(&env) {
+            return Err(VaultError::ExceedsAccruedRevenue);
+        }
+        let usdc = token::Client::new(&env, &Self::get_usdc(&env)?);
+        let vault_address = env.current_contract_address();
+        if usdc.balance(&vault_address) < total {
+            return Err(VaultError::InsufficientUsdc);
+        }
+
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, ACCRUED_REVENUE_KEY), &(accrued - total));
+        for (to, amount) in payouts.iter() {
+            usdc.transfer(&vault_address, &to, &amount);
+            env.events()
+                .publish((Symbol::new(&env, "distribute"), to), amount);
+        }
+        env.events().publish(
+            (Symbol::new(&env, "batch_distribute"), caller),
+            (payouts.len(), total),
+        );
+        Ok(total)
+    }
+
+    /// Get vault metadata (owner and balance).
+    pub fn get_meta(env: Env) -> Result<VaultMeta, VaultError> {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, META_KEY))
+            .ok_or(VaultError::NotInitialized)
+    }
+
+    /// Deposit: user transfers USDC to the contract; contract increases internal balance.
+    /// Caller must have authorized the transfer (token transfer_from). Supports multiple depositors;
+    /// the amount is credited to `from`'s sub-account (see `balance_of`).
+    /// Fails with `BelowMinDeposit` if amount is below the vault's min_deposit.
+    /// Emits a "deposit" event with the depositor address and amount.
+    pub fn deposit(env: Env, from: Address, amount: i128) -> Result<i128, VaultError> {
+        Self::extend_instance_ttl(&env);
+        from.require_auth();
+
+        let mut meta = Self::get_meta(env.clone())?;
+        if Self::get_pause_state(env.clone()).deposit {
+            return Err(VaultError::Paused);
+        }
+        Self::require_current_schema(&env)?;
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        if amount < meta.min_deposit {
+            return Err(VaultError::BelowMinDeposit);
+        }
+
+        let usdc = token::Client::new(&env, &Self::get_usdc(&env)?);
+        usdc.transfer_from(
+            &env.current_contract_address(),
+            &from,
+            &env.current_contract_address(),
+            &amount,
+        );
+
+        meta.balance += amount;
+        let inst = env.storage().instance();
+        inst.set(&Symbol::new(&env, "meta"), &meta);
+        let sub_balance = Self::balance_of(env.clone(), from.clone()) + amount;
+        Self::set_sub_balance(&env, &from, sub_balance);
+
+        env.events()
+            .publish((Symbol::new(&env, "deposit"), from), amount);
+
+        Ok(meta.balance)
+    }
+
+    /// Deduct balance for an API call from the vault owner's sub-account.
+    /// Callable only by an authorized deducter (see `add_deducter`).
+    /// Same rules and events as `deduct_from` with `depositor` set to the owner.
+    pub fn deduct(
+        env: Env,
+        caller: Address,
+        amount: i128,
+        request_id: Option<Symbol>,
+    ) -> Result<i128, VaultError> {
+        let owner = Self::get_meta(env.clone())?.owner;
+        Self::deduct_from(env, caller, owner, amount, request_id)
+    }
+
+    /// Deduct balance for an API call from `depositor`'s sub-account.
+    /// Callable only by an authorized deducter (see `add_deducter`).
+    /// Amount must not exceed max single deduct (see init / get_max_deduct).
+    /// If revenue pool is set, USDC is transferred to it and the pool is notified via
+    /// `receive_payment`; otherwise it remains in the vault as accrued revenue.
+    /// A `request_id` may only be charged once within the retention window (see
+    /// `get_request_ttl`); replays fail with `DuplicateRequest` so backends can retry safely.
+    /// If a platform fee is set (see `set_platform_fee`), its share goes to the treasury
+    /// and only the remainder is settled as revenue.
+    /// Emits a "deduct" event with caller, optional request_id, depositor, amount, new balance,
+    /// fee, and revenue.
+    pub fn deduct_from(
+        env: Env,
+        caller: Address,
+        depositor: Address,
+        amount: i128,
+        request_id: Option<Symbol>,
+    ) -> Result<i128, VaultError> {
+        Self::extend_instance_ttl(&env);
+        caller.require_auth();
+        Self::require_deducter(&env, &caller)?;
+        if Self::get_pause_state(env.clone()).deduct {
+            return Err(VaultError::Paused);
+        }
+        Self::require_current_schema(&env)?;
+        let max_deduct = Self::get_max_deduct(env.clone())?;
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        if amount > max_deduct {
+            return Err(VaultError::ExceedsMaxDeduct);
+        }
+
+        let mut meta = Self::get_meta(env.clone())?;
+        let sub_balance = Self::balance_of(env.clone(), depositor.clone());
+        if sub_balance - Self::held_amount(&env, &depositor) < amount {
+            return Err(VaultError::InsufficientBalance);
+        }
+        if let Some(rid) = &request_id {
+            if Self::is_processed(env.clone(), rid.clone()) {
+                return Err(VaultError::DuplicateRequest);
+            }
+        }
+        Self::charge_budget(&env, amount)?;
+
+        meta.balance -= amount;
+        let inst = env.storage().instance();
+        inst.set(&Symbol::new(&env, "meta"), &meta);
+        Self::set_sub_balance(&env, &depositor, sub_balance - amount);
+        let fee = Self::platform_fee(&env, amount);
+        if let Some(rid) = &request_id {
+            Self::record_request(&env, rid, amount, fee, &depositor);
+        }
+        Self::settle(&env, amount, fee)?;
+
+        Self::publish_deduct(
+            &env,
+            &caller,
+            &request_id,
+            &depositor,
+            amount,
+            meta.balance,
+            fee,
+        );
+        Ok(meta.balance)
+    }
+
+    /// Batch deduct: multiple (amount, optional request_id, optional depositor) in one transaction.
+    /// Callable only by an authorized deducter. Each amount must not exceed max_deduct and each
+    /// item is charged to its depositor's sub-account (the owner's when `depositor` is `None`).
+    /// Reverts entire batch if any check fails, including a request_id that was already
+    /// processed or appears twice within the batch.
+    /// If revenue pool is set, total deducted USDC is transferred to it once and the pool
+    /// is notified once with the batch total. The platform fee is computed per item and
+    /// sent to the treasury in one transfer.
+    /// Emits one "deduct" event per item.
+    pub fn batch_deduct(
+        env: Env,
+        caller: Address,
+        items: Vec<DeductItem>,
+    ) -> Result<i128, VaultError> {
+        Self::extend_instance_ttl(&env);
+        caller.require_auth();
+        Self::require_deducter(&env, &caller)?;
+        if Self::get_pause_state(env.clone()).batch_deduct {
+            return Err(VaultError::Paused);
+        }
+        Self::require_current_schema(&env)?;
+        let max_deduct = Self::get_max_deduct(env.clone())?;
+        let mut meta = Self::get_meta(env.clone())?;
+        if items.is_empty() {
+            return Err(VaultError::EmptyBatch);
+        }
+
+        let mut total_deduct = 0i128;
+        let mut running: Map<Address, i128> = Map::new(&env);
+        let mut seen: Vec<Symbol> = Vec::new(&env);
+        for item in items.iter() {
+            if item.amount <= 0 {
+                return Err(VaultError::InvalidAmount);
+            }
+            if item.amount > max_deduct {
+                return Err(VaultError::ExceedsMaxDeduct);
+            }
+            let depositor = item.depositor.clone().unwrap_or(meta.owner.clone());
+            let sub_balance = running
+                .get(depositor.clone())
+                .unwrap_or_else(|| Self::balance_of(env.clone(), depositor.clone()));
+            if sub_balance - Self::held_amount(&env, &depositor) < item.amount {
+                return Err(VaultError::InsufficientBalance);
+            }
+            if let Some(rid) = &item.request_id {
+                if seen.contains(rid) || Self::is_processed(env.clone(), rid.clone()) {
+                    return Err(VaultError::DuplicateRequest);
+                }
+                seen.push_back(rid.clone());
+            }
+            running.set(depositor, sub_balance - item.amount);
+            total_deduct += item.amount;
+        }
+        Self::charge_budget(&env, total_deduct)?;
+
+        let mut balance = meta.balance;
+        let mut total_fee = 0i128;
+        for item in items.iter() {
+            balance -= item.amount;
+            let depositor = item.depositor.clone().unwrap_or(meta.owner.clone());
+            let fee = Self::platform_fee(&env, item.amount);
+            if let Some(rid) = &item.request_id {
+                Self::record_request(&env, rid, item.amount, fee, &depositor);
+            }
+            total_fee += fee;
+            Self::publish_deduct(
+                &env,
+                &caller,
+                &item.request_id,
+                &depositor,
+                item.amount,
+                balance,
+                fee,
+            );
+        }
+        for (depositor, sub_balance) in running.iter() {
+            Self::set_sub_balance(&env, &depositor, sub_balance);
+        }
+
+        meta.balance = balance;
+        let inst = env.storage().instance();
+        inst.set(&Symbol::new(&env, "meta"), &meta);
+
+        Self::settle(&env, total_deduct, total_fee)?;
+        Ok(meta.balance)
+    }
+
+    /// Give back up to the revenue part (amount minus platform fee) of the deduct recorded
+    /// for `request_id`, e.g. after an overcharge or a failed upstream call. Callable by the
+    /// admin or an authorized deducter. The funds are pulled back from the revenue pool (via
+    /// `refund_payment`) or, with no pool, taken out of accrued revenue, and credited to the
+    /// charged sub-account. The platform fee stays with the treasury, so developers never
+    /// pay it back. Only deducts still within the request-id retention window can be refunded.
+    /// Emits a "refund" event keyed by caller, request_id and depositor.
+    ///
+    /// # Errors
+    /// * `RequestNotFound`       – no deduct recorded for `request_id`.
+    /// * `ExceedsDeducted`       – cumulative refunds would exceed `amount - fee`.
+    /// * `ExceedsAccruedRevenue` – no pool and accrued revenue is already distributed.
+    pub fn refund(
+        env: Env,
+        caller: Address,
+        request_id: Symbol,
+        amount: i128,
+    ) -> Result<i128, VaultError> {
+        Self::extend_instance_ttl(&env);
+        caller.require_auth();
+        if caller != Self::get_admin(env.clone())? {
+            Self::require_deducter(&env, &caller)?;
+        }
+        Self::require_current_schema(&env)?;
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        let key = (Symbol::new(&env, REQUEST_KEY), request_id.clone());
+        let mut record: DeductRecord = env
+            .storage()
+            .temporary()
+            .get(&key)
+            .ok_or(VaultError::RequestNotFound)?;
+        if record.refunded + amount > record.amount - record.fee {
+            return Err(VaultError::ExceedsDeducted);
+        }
+        if Self::get_dispute(env.clone(), request_id.clone()).is_some() {
+            return Err(VaultError::DisputeOpen);
+        }
+
+        match Self::get_revenue_pool(env.clone()) {
+            Some(pool) => RevenuePoolClient::new(&env, &pool)
+                .refund_payment(&env.current_contract_address(), &amount),
+            None => {
+                let accrued = Self::get_accrued_revenue(env.clone());
+                if amount > accrued - Self::get_frozen_revenue(&env) {
+                    return Err(VaultError::ExceedsAccruedRevenue);
+                }
+                env.storage()
+                    .instance()
+                    .set(&Symbol::new(&env, ACCRUED_REVENUE_KEY), &(accrued - amount));
+            }
+        }
+
+        record.refunded += amount;
+        env.storage().temporary().set(&key, &record);
+        let mut meta = Self::get_meta(env.clone())?;
+        meta.balance += amount;
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, "meta"), &meta);
+        let sub_balance = Self::balance_of(env.clone(), record.depositor.clone()) + amount;
+        Self::set_sub_balance(&env, &record.depositor, sub_balance);
+
+        env.events().publish(
+            (
+                Symbol::new(&env, "refund"),
+                caller,
+                request_id,
+                record.depositor,
+            ),
+            (amount, meta.balance),
+        );
+        Ok(meta.balance)
+    }
+
+    /// Return the number of ledgers after a deduct during which the owner may dispute it.
+    pub fn get_dispute_window(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, DISPUTE_WINDOW_KEY))
+            .unwrap_or(DEFAULT_DISPUTE_WINDOW)
+    }
+
+    /// Set the dispute window in ledgers. Admin only; zero fails with `InvalidConfig`.
+    /// Disputes also need the deduct record, so windows longer than the request-id
+    /// retention (see `get_request_ttl`) are effectively capped by it.
+    pub fn set_dispute_window(env: Env, caller: Address, ledgers: u32) -> Result<(), VaultError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_admin(&env, &caller)?;
+        if ledgers == 0 {
+            return Err(VaultError::InvalidConfig);
+        }
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, DISPUTE_WINDOW_KEY), &ledgers);
+        Ok(())
+    }
+
+    /// Dispute a recorded deduct. Owner only, within the dispute window.
+    /// The unrefunded revenue part of the charge (excluding the platform fee) is frozen — in the revenue pool via
+    /// `freeze_payment`, or in accrued revenue when there is no pool — until the admin
+    /// calls `resolve_dispute`. Refunds for the request_id are blocked meanwhile.
+    /// Emits a "dispute_opened" event with owner, request_id, reason_code and amount.
+    ///
+    /// # Errors
+    /// * `RequestNotFound`     – no deduct recorded for `request_id`.
+    /// * `DisputeOpen`         – the request_id is already disputed.
+    /// * `DisputeWindowClosed` – the window after the deduct has passed.
+    /// * `InvalidAmount`       – the refundable part was already fully refunded.
+    pub fn dispute(env: Env, request_id: Symbol, reason_code: u32) -> Result<Dispute, VaultError> {
+        Self::extend_instance_ttl(&env);
+        let meta = Self::get_meta(env.clone())?;
+        meta.owner.require_auth();
+        Self::require_current_schema(&env)?;
+        let record = Self::get_deduct_record(env.clone(), request_id.clone())
+            .ok_or(VaultError::RequestNotFound)?;
+        if Self::get_dispute(env.clone(), request_id.clone()).is_some() {
+            return Err(VaultError::DisputeOpen);
+        }
+        let window = Self::get_dispute_window(env.clone());
+        if env.ledger().sequence() > record.deducted_at.saturating_add(window) {
+            return Err(VaultError::DisputeWindowClosed);
+        }
+        let amount = record.amount - record.fee - record.refunded;
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+
+        match Self::get_revenue_pool(env.clone()) {
+            Some(pool) => RevenuePoolClient::new(&env, &pool)
+                .freeze_payment(&env.current_contract_address(), &amount),
+            None => {
+                let frozen = Self::get_frozen_revenue(&env);
+                if amount > Self::get_accrued_revenue(env.clone()) - frozen {
+                    return Err(VaultError::ExceedsAccruedRevenue);
+                }
+                env.storage()
+                    .instance()
+                    .set(&Symbol::new(&env, FROZEN_REVENUE_KEY), &(frozen + amount));
+            }
+        }
+
+        let dispute = Dispute {
+            depositor: record.depositor,
+            amount,
+            reason_code,
+            opened_at: env.ledger().sequence(),
+        };
+        let key = (Symbol::new(&env, DISPUTE_KEY), request_id.clone());
+        env.storage().persistent().set(&key, &dispute);
+        Self::extend_persistent_ttl(&env, &key);
+
+        env.events().publish(
+            (Symbol::new(&env, "dispute_opened"), meta.owner, request_id),
+            (reason_code, amount),
+        );
+        Ok(dispute)
+    }
+
+    /// Close an open dispute, refunding `refund_bps` basis points (0–10_000) of the frozen
+    /// amount to the charged sub-account and releasing the rest back to the revenue side.
+    /// Admin only. Emits a "dispute_resolved" event with the refunded and released amounts.
+    pub fn resolve_dispute(
+        env: Env,
+        caller: Address,
+        request_id: Symbol,
+        refund_bps: u32,
+    ) -> Result<i128, VaultError> {
+        Self::extend_instance_ttl(&env);
+        Self::require_admin(&env, &caller)?;
+        Self::require_current_schema(&env)?;
+        if refund_bps > BPS_DENOMINATOR {
+            return Err(VaultError::InvalidConfig);
+        }
+        let dispute = Self::get_dispute(env.clone(), request_id.clone())
+            .ok_or(VaultError::DisputeNotFound)?;
+        let refund_amount = dispute.amount * refund_bps as i128 / BPS_DENOMINATOR as i128;
+
+        match Self::get_revenue_pool(env.clone()) {
+            Some(pool) => {
+                let pool_client = RevenuePoolClient::new(&env, &pool);
+                let vault_address = env.current_contract_address();
+                pool_client.unfreeze_payment(&vault_address, &dispute.amount);
+                if refund_amount > 0 {
+                    pool_client.refund_payment(&vault_address, &refund_amount);
+                }
+            }
+            None => {
+                let inst = env.storage().instance();
+                let frozen = Self::get_frozen_revenue(&env) - dispute.amount;
+                inst.set(&Symbol::new(&env, FROZEN_REVENUE_KEY), &frozen);
+                let accrued = Self::get_accrued_revenue(env.clone()) - refund_amount;
+                inst.set(&Symbol::new(&env, ACCRUED_REVENUE_KEY), &accrued);
+            }
+        }
+
+        env.storage()
+            .persistent()
+            .remove(&(Symbol::new(&env, DISPUTE_KEY), request_id.clone()));
+        if let Some(mut record) = Self::get_deduct_record(env.clone(), request_id.clone()) {
+            record.refunded += refund_amount;
+            env.storage().temporary().set(
+                &(Symbol::new(&env, REQUEST_KEY), request_id.clone()),
+                &record,
+            );
+        }
+        let mut meta = Self::get_meta(env.clone())?;
+        meta.balance += refund_amount;
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, "meta"), &meta);
+        let sub_balance = Self::balance_of(env.clone(), dispute.depositor.clone()) + refund_amount;
+        Self::set_sub_balance(&env, &dispute.depositor, sub_balance);
+
+        env.events().publish(
+            (Symbol::new(&env, "dispute_resolved"), caller, request_id),
+            (refund_amount, dispute.amount - refund_amount),
+        );
+        Ok(meta.balance)
+    }
+
+    /// Return the open dispute for `request_id`, if any.
+    pub fn get_dispute(env: Env, request_id: Symbol) -> Option<Dispute> {
+        env.storage()
+            .persistent()
+            .get(&(Symbol::new(&env, DISPUTE_KEY), request_id))
+    }
+
+    /// Return the recorded deduct for `request_id`, if still within the retention window.
+    pub fn get_deduct_record(env: Env, request_id: Symbol) -> Option<DeductRecord> {
+        env.storage()
+            .temporary()
+            .get(&(Symbol::new(&env, REQUEST_KEY), request_id))
+    }
+
+    /// Set or clear (`None`) the periodic spending cap. Owner only.
+    /// Once set, `deduct`, `deduct_from`, `batch_deduct` and `capture` fail with
+    /// `BudgetExceeded` if they would charge more than `limit` within the current window.
+    /// Windows are fixed and aligned to multiples of the period on the ledger timestamp;
+    /// spending resets at each boundary, so the cap does not bound a rolling interval.
+    /// Emits a "spend_cap_set" event with the owner and the new cap.
+    pub fn set_spend_cap(env: Env, cap: Option<SpendCap>) -> Result<(), VaultError> {
+        Self::extend_instance_ttl(&env);
+        let meta = Self::get_meta(env.clone())?;
+        meta.owner.require_auth();
+        let inst = env.storage().instance();
+        match &cap {
+            Some(c) => {
+                if c.limit <= 0 {
+                    return Err(VaultError::InvalidConfig);
+                }
+                inst.set(&Symbol::new(&env, SPEND_CAP_KEY), c);
+            }
+            None => inst.remove(&Symbol::new(&env, SPEND_CAP_KEY)),
+        }
+
+        env.events()
+            .publish((Symbol::new(&env, "spend_cap_set"), meta.owner), cap);
+        Ok(())
+    }
+
+    /// Return the configured spending cap, if any.
+    pub fn get_spend_cap(env: Env) -> Option<SpendCap> {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, SPEND_CAP_KEY))
+    }
+
+    /// Report spent vs. remaining budget for the current fixed window; `None` when no cap is set.
+    pub fn get_spend_window(env: Env) -> Option<SpendWindow> {
+        let cap = Self::get_spend_cap(env.clone())?;
+        let period_secs = cap.period.seconds();
+        let now = env.ledger().timestamp();
+        let window_start = now - now % period_secs;
+        let (stored_start, stored_spent): (u64, i128) = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(&env, SPENT_KEY))
+            .unwrap_or((0, 0));
+        let spent = if stored_start == window_start {
+            stored_spent
+        } else {
+            0
+        };
+        Some(SpendWindow {
+            period: cap.period,
+            limit: cap.limit,
+            window_start,
+            spent,
+            remaining: (cap.limit - spent).max(0),
+        })
+    }
+
+    /// Reserve `amount` from the owner's sub-account ahead of a long-running API call.
+    /// Callable only by an authorized deducter; held funds cannot be deducted or withdrawn
+    /// until the hold is captured, released, or passes `expires_at_ledger`, after which it is
+    /// released automatically (`expire_hold` removes the lapsed record; reusing its id does
+    /// too). At most `MAX_OPEN_HOLDS` unexpired holds may be open at once. Subject to
+    /// `max_deduct` and the `deduct` pause flag.
+    /// Emits a "hold" event with caller, hold_id, amount, and expiry.
+    ///
+    /// # Errors
+    /// * `InvalidConfig`       – `expires_at_ledger` is not in the future.
+    /// * `DuplicateHold`       – an unexpired hold already uses `hold_id`.
+    /// * `InsufficientBalance` – less than `amount` is available to hold.
+    /// * `TooManyHolds`        – `MAX_OPEN_HOLDS` unexpired holds are already open.
+    pub fn hold(
+        env: Env,
+        caller: Address,
+        amount: i128,
+        hold_id: Symbol,
+        expires_at_ledger: u32,
+    ) -> Result<Hold, VaultError> {
+        Self::extend_instance_ttl(&env);
+        caller.require_auth();
+        Self::require_deducter(&env, &caller)?;
+        if Self::get_pause_state(env.clone()).deduct {
+            return Err(VaultError::Paused);
+        }
+        Self::require_current_schema(&env)?;
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        if amount > Self::get_max_deduct(env.clone())? {
+            return Err(VaultError::ExceedsMaxDeduct);
+        }
+        if expires_at_ledger <= env.ledger().sequence() {
+            return Err(VaultError::InvalidConfig);
+        }
+
+        let depositor = Self::get_meta(env.clone())?.owner;
+        if let Some(existing) = Self::get_hold(env.clone(), hold_id.clone()) {
+            if existing.expires_at >= env.ledger().sequence() {
+                return Err(VaultError::DuplicateHold);
+            }
+            Self::drop_hold(&env, &hold_id, &existing, &depositor);
+            env.events().publish(
+                (
+                    Symbol::new(&env, "hold_expired"),
+                    existing.caller,
+                    hold_id.clone(),
+                ),
+                existing.amount,
+            );
+        }
+        let available =
+            Self::balance_of(env.clone(), depositor.clone()) - Self::held_amount(&env, &depositor);
+        if available < amount {
+            return Err(VaultError::InsufficientBalance);
+        }
+
+        let hold = Hold {
+            caller: caller.clone(),
+            amount,
+            expires_at: expires_at_ledger,
+        };
+        let key = (Symbol::new(&env, HOLDS_KEY), hold_id.clone());
+        env.storage().persistent().set(&key, &hold);
+        Self::extend_persistent_ttl(&env, &key);
+        Self::add_held(&env, &depositor, amount, expires_at_ledger)?;
+
+        env.events().publish(
+            (Symbol::new(&env, "hold"), caller, hold_id),
+            (amount, expires_at_ledger),
+        );
+        Ok(hold)
+    }
+
+    /// Settle a hold for `final_amount` (at most the held amount) and free the remainder.
+    /// Must be signed by the deducter that placed the hold, which must still be on the
+    /// allowlist. The captured amount is deducted from the owner's sub-account and settled exactly like `deduct` (revenue pool or accrued
+    /// revenue). Emits a "capture" event with hold_id, captured amount, and new balance.
+    ///
+    /// # Errors
+    /// * `HoldNotFound`  – no hold for `hold_id`.
+    /// * `HoldExpired`   – the hold passed its expiry and can no longer be captured.
+    /// * `InvalidAmount` – `final_amount` is zero, negative, or above the held amount.
+    pub fn capture(env: Env, hold_id: Symbol, final_amount: i128) -> Result<i128, VaultError> {
+        Self::extend_instance_ttl(&env);
+        let hold = Self::get_hold(env.clone(), hold_id.clone()).ok_or(VaultError::HoldNotFound)?;
+        hold.caller.require_auth();
+        Self::require_deducter(&env, &hold.caller)?;
+        if Self::get_pause_state(env.clone()).deduct {
+            return Err(VaultError::Paused);
+        }
+        Self::require_current_schema(&env)?;
+        if env.ledger().sequence() > hold.expires_at {
+            return Err(VaultError::HoldExpired);
+        }
+        if final_amount <= 0 || final_amount > hold.amount {
+            return Err(VaultError::InvalidAmount);
+        }
+        if final_amount > Self::get_max_deduct(env.clone())? {
+            return Err(VaultError::ExceedsMaxDeduct);
+        }
+        Self::charge_budget(&env, final_amount)?;
+
+        let mut meta = Self::get_meta(env.clone())?;
+        Self::drop_hold(&env, &hold_id, &hold, &meta.owner);
+        meta.balance -= final_amount;
+        env.storage()
+            .instance()
+            .set(&Symbol::new(&env, "meta"), &meta);
+        let sub_balance = Self::balance_of(env.clone(), meta.owner.clone());
+        Self::set_sub_balance(&env, &meta.owner, sub_balance - final_amount);
+
+        Self::settle(&env, final_amount, Self::platform_fee(&env, final_amount))?;
+
+        env.events().publish(
+            (Symbol::new(&env, "capture"), hold.caller, hold_id),
+            (final_amount, meta.balance),
+        );
+        Ok(meta.balance)
+    }
+
+    /// Cancel a hold and return its funds to the available balance.
+    /// Must be signed by the deducter that placed the hold. Emits a "release" event.
+    pub fn release(env: Env, hold_id: Symbol) -> Result<(), VaultError> {
+        Self::extend_instance_ttl(&env);
+        let hold = Self::get_hold(env.clone(), hold_id.clone()).ok_or(VaultError::HoldNotFound)?;
+        hold.caller.require_auth();
+
+        let owner = Self::get_meta(env.clone())?.owner;
+        Self::drop_hold(&env, &hold_id, &hold, &owner);
+
+        env.events().publish(
+            (Symbol::new(&env, "release"), hold.caller, hold_id),
+            hold.amount,
+        );
+        Ok(())
+    }
+
+    /// Remove the record of a hold that has passed its expiry ledger; its funds stopped
+    /// counting against the balance at expiry. Callable by anyone. Emits a "hold_expired" event.
+    ///
+    /// # Errors
+    /// * `HoldNotFound` – no hold for `hold_id`.
+    /// * `HoldActive`   – the hold has not expired yet.
+    pub fn expire_hold(env: Env, hold_id: Symbol) -> Result<(), VaultError> {
+        Self::extend_instance_ttl(&env);
+        let hold = Self::get_hold(env.clone(), hold_id.clone()).ok_or(VaultError::HoldNotFound)?;
+        if hold.expires_at >= env.ledger().sequence() {
+            return Err(VaultError::HoldActive);
+        }
+
+        let owner = Self::get_meta(env.clone())?.owner;
+        Self::drop_hold(&env, &hold_id, &hold, &owner);
+
+        env.events().publish(
+            (Symbol::new(&env, "hold_expired"), hold.caller, hold_id),
+            hold.amount,
+        );
+        Ok(())
+    }
+
+    /// Return the hold stored under `hold_id`, if any (expired holds linger until expired,
+    /// released or replaced).
+    pub fn get_hold(env: Env, hold_id: Symbol) -> Option<Hold> {
+        env.storage()
+            .persistent()
+            .get(&(Symbol::new(&env, HOLDS_KEY), hold_id))
+    }
+
+    /// Withdraw from vault. Callable only by the vault owner; reduces the owner's sub-account
+    /// and transfers USDC to owner. Other depositors' funds are not reachable this way.
+    pub fn withdraw(env: Env, amount: i128) -> Result<i128, VaultError> {
+        let owner = Self::get_meta(env.clone())?.owner;
+        Self::withdraw_deposit(env, owner, amount)
+    }
+
+    /// Withdraw from the owner's sub-account to a designated address. Owner-only; transfers USDC to `to`.
+    pub fn withdraw_to(env: Env, to: Address, amount: i128) -> Result<i128, VaultError> {
+        Self::extend_instance_ttl(&env);
+        let mut meta = Self::get_meta(env.clone())?;
+        meta.owner.require_auth();
+        if Self::get_pause_state(env.clone()).withdraw {
+            return Err(VaultError::Paused);
+        }
+        Self::require_current_schema(&env)?;
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        let sub_balance = Self::balance_of(env.clone(), meta.owner.clone());
+        if sub_balance - Self::held_amount(&env, &meta.owner) < amount {
+            return Err(VaultError::InsufficientBalance);
+        }
+
+        let usdc = token::Client::new(&env, &Self::get_usdc(&env)?);
+        usdc.transfer(&env.current_contract_address(), &to, &amount);
+
+        meta.balance -= amount;
+        let inst = env.storage().instance();
+        inst.set(&Symbol::new(&env, "meta"), &meta);
+        Self::set_sub_balance(&env, &meta.owner, sub_balance - amount);
+
+        env.events().publish(
+            (
+                Symbol::new(&env, "withdraw_to"),
+                meta.owner.clone(),
+                to.clone(),
+            ),
+            (amount, meta.balance),
+        );
+        Ok(meta.balance)
+    }
+
+    /// Withdraw `amount` of `depositor`'s own remaining funds back to `depositor`.
+    /// Must be signed by `depositor`; honours the `withdraw` pause flag.
+    /// Emits a "withdraw" event keyed by the depositor.
+    pub fn withdraw_deposit(
+        env: Env,
+        depositor: Address,
+        amount: i128,
+    ) -> Result<i128, VaultError> {
+        Self::extend_instance_ttl(&env);
+        let mut meta = Self::get_meta(env.clone())?;
+        depositor.require_auth();
+        if Self::get_pause_state(env.clone()).withdraw {
+            return Err(VaultError::Paused);
+        }
+        Self::require_current_schema(&env)?;
+        if amount <= 0 {
+            return Err(VaultError::InvalidAmount);
+        }
+        let sub_balance = Self::balance_of(env.clone(), depositor.clone());
+        if sub_balance - Self::held_amount(&env, &depositor) < amount {
+            return Err(VaultError::InsufficientBalance);
+        }
+
+        let usdc = token::Client::new(&env, &Self::get_usdc(&env)?);
+        usdc.transfer(&env.current_contract_address(), &depositor, &amount);
+
+        meta.balance -= amount;
+        let inst = env.storage().instance();
+        inst.set(&Symbol::new(&env, "meta"), &meta);
+        Self::set_sub_balance(&env, &depositor, sub_balance - amount);
+
+        env.events().publish(
+            (Symbol::new(&env, "withdraw"), depositor),
+            (amount, meta.balance),
+        );
+        Ok(meta.balance)
+    }
+
+    /// Return `depositor`'s sub-account balance (0 if they never deposited).
+    pub fn balance_of(env: Env, depositor: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&(Symbol::new(&env, DEPOSITOR_KEY), depositor))
+            .unwrap_or(0)
+    }
+
+    /// Return the balance available to spend: the sum of all sub-accounts minus held funds.
+    pub fn balance(env: Env) -> Result<i128, VaultError> {
+        let meta = Self::get_meta(env.clone())?;
+        Ok(meta.balance - Self::held_amount(&env, &meta.owner))
+    }
+}
+
+impl CalloraVault {
+    /// Require `caller`'s signature and that it is the current admin.
+    fn require_admin(env: &Env, caller: &Address) -> Result<(), VaultError> {
+        caller.require_auth();
+        if *caller != Self::get_admin(env.clone())? {
+            return Err(VaultError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    /// Fail with `MigrationPending` while storage is older than `SCHEMA_VERSION`, so balances
+    /// cannot move between `upgrade` and `migrate` (which rewrites the owner's sub-account).
+    fn require_current_schema(env: &Env) -> Result<(), VaultError> {
+        if Self::get_schema_version(env.clone()) < SCHEMA_VERSION {
+            return Err(VaultError::MigrationPending);
+        }
+        Ok(())
+    }
+
+    /// Fail with `Unauthorized` unless `caller` is on the deducter allowlist.
+    /// Active deducters have their allowlist entry's TTL extended.
+    fn require_deducter(env: &Env, caller: &Address) -> Result<(), VaultError> {
+        if !Self::is_deducter(env.clone(), caller.clone()) {
+            return Err(VaultError::Unauthorized);
+        }
+        Self::extend_persistent_ttl(env, &(Symbol::new(env, DEDUCTER_KEY), caller.clone()));
+        Ok(())
+    }
+
+    /// Extend the instance (and contract code) TTL per the configured policy.
+    fn extend_instance_ttl(env: &Env) {
+        let config = Self::get_ttl_config(env.clone());
+        env.storage()
+            .instance()
+            .extend_ttl(config.threshold, config.extend_to);
+    }
+
+    /// Extend a persistent entry's TTL per the configured policy.
+    fn extend_persistent_ttl<K: IntoVal<Env, Val>>(env: &Env, key: &K) {
+        let config = Self::get_ttl_config(env.clone());
+        env.storage()
+            .persistent()
+            .extend_ttl(key, config.threshold, config.extend_to);
+    }
+
+    /// Return the configured USDC token address.
+    fn get_usdc(env: &Env) -> Result<Address, VaultError> {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(env, USDC_KEY))
+            .ok_or(VaultError::NotInitialized)
+    }
+
+    /// Remember that `request_id` was deducted from `depositor` for the retention window.
+    fn record_request(
+        env: &Env,
+        request_id: &Symbol,
+        amount: i128,
+        fee: i128,
+        depositor: &Address,
+    ) {
+        let key = (Symbol::new(env, REQUEST_KEY), request_id.clone());
+        let ttl = Self::get_request_ttl(env.clone());
+        let temp = env.storage().temporary();
+        let record = DeductRecord {
+            amount,
+            fee,
+            depositor: depositor.clone(),
+            refunded: 0,
+            deducted_at: env.ledger().sequence(),
+        };
+        temp.set(&key, &record);
+        temp.extend_ttl(&key, ttl, ttl);
+    }
+
+    /// Store `depositor`'s sub-account balance, dropping the entry once it reaches zero.
+    fn set_sub_balance(env: &Env, depositor: &Address, amount: i128) {
+        let key = (Symbol::new(env, DEPOSITOR_KEY), depositor.clone());
+        if amount == 0 {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &amount);
+            Self::extend_persistent_ttl(env, &key);
+        }
+    }
+
+    /// Check that `min_deposit` is non-negative, `max_deduct` positive, and the minimum
+    /// deposit does not exceed the single-deduct cap.
+    fn validate_limits(min_deposit: i128, max_deduct: i128) -> Result<(), VaultError> {
+        if min_deposit < 0 || max_deduct <= 0 || min_deposit > max_deduct {
+            return Err(VaultError::InvalidConfig);
+        }
+        Ok(())
+    }
+
+    /// Count `amount` against the spending cap, if one is set. A refusal reverts the whole
+    /// call, so it is reported only through the `BudgetExceeded` error (no event survives).
+    fn charge_budget(env: &Env, amount: i128) -> Result<(), VaultError> {
+        let window = match Self::get_spend_window(env.clone()) {
+            Some(w) => w,
+            None => return Ok(()),
+        };
+        if amount > window.remaining {
+            return Err(VaultError::BudgetExceeded);
+        }
+        env.storage().instance().set(
+            &Symbol::new(env, SPENT_KEY),
+            &(window.window_start, window.spent + amount),
+        );
+        Ok(())
+    }
+
+    /// Accrued revenue frozen by open disputes (vaults without a revenue pool).
+    fn get_frozen_revenue(env: &Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(env, FROZEN_REVENUE_KEY))
+            .unwrap_or(0)
+    }
+
+    /// `(amount, expires_at)` of each hold placed on `depositor`'s sub-account that has not
+    /// been removed; lapsed entries are dropped when the next hold is placed.
+    fn get_held(env: &Env, depositor: &Address) -> Vec<(i128, u32)> {
+        env.storage()
+            .persistent()
+            .get(&(Symbol::new(env, HELD_KEY), depositor.clone()))
+            .unwrap_or(Vec::new(env))
+    }
+
+    fn set_held(env: &Env, depositor: &Address, held: Vec<(i128, u32)>) {
+        let key = (Symbol::new(env, HELD_KEY), depositor.clone());
+        if held.is_empty() {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &held);
+            Self::extend_persistent_ttl(env, &key);
+        }
+    }
+
+    /// Funds held from `depositor`'s sub-account by holds that have not expired.
+    fn held_amount(env: &Env, depositor: &Address) -> i128 {
+        let now = env.ledger().sequence();
+        Self::get_held(env, depositor)
+            .iter()
+            .filter(|(_, expires_at)| *expires_at >= now)
+            .map(|(amount, _)| amount)
+            .sum()
+    }
+
+    /// Count a new hold against `depositor`, dropping lapsed entries first.
+    /// Fails with `TooManyHolds` if `MAX_OPEN_HOLDS` unexpired holds are already open.
+    fn add_held(
+        env: &Env,
+        depositor: &Address,
+        amount: i128,
+        expires_at: u32,
+    ) -> Result<(), VaultError> {
+        let now = env.ledger().sequence();
+        let mut held = Vec::new(env);
+        for entry in Self::get_held(env, depositor).iter() {
+            if entry.1 >= now {
+                held.push_back(entry);
+            }
+        }
+        if held.len() >= MAX_OPEN_HOLDS {
+            return Err(VaultError::TooManyHolds);
+        }
+        held.push_back((amount, expires_at));
+        Self::set_held(env, depositor, held);
+        Ok(())
+    }
+
+    /// Delete a hold and its entry in `depositor`'s held list. An entry already dropped as
+    /// lapsed is simply absent; any other entry with the same values has lapsed too.
+    fn drop_hold(env: &Env, hold_id: &Symbol, hold: &Hold, depositor: &Address) {
+        env.storage()
+            .persistent()
+            .remove(&(Symbol::new(env, HOLDS_KEY), hold_id.clone()));
+        let mut held = Self::get_held(env, depositor);
+        if let Some(index) = held.first_index_of((hold.amount, hold.expires_at)) {
+            held.remove(index);
+            Self::set_held(env, depositor, held);
+        }
+    }
+
+    /// Emit a "deduct" event keyed by caller, request_id (empty symbol if none) and depositor.
+    fn publish_deduct(
+        env: &Env,
+        caller: &Address,
+        request_id: &Option<Symbol>,
+        depositor: &Address,
+        amount: i128,
+        balance: i128,
+        fee: i128,
+    ) {
+        let rid = request_id.clone().unwrap_or(Symbol::new(env, ""));
+        env.events().publish(
+            (
+                Symbol::new(env, "deduct"),
+                caller.clone(),
+                rid,
+                depositor.clone(),
+            ),
+            (amount, balance, fee, amount - fee),
+        );
+    }
+
+    /// Platform fee owed on a deduct of `amount`, rounded down. Zero when no fee is set.
+    fn platform_fee(env: &Env, amount: i128) -> i128 {
+        match Self::get_platform_fee(env.clone()) {
+            Some(fee) => amount * fee.fee_bps as i128 / BPS_DENOMINATOR as i128,
+            None => 0,
+        }
+    }
+
+    /// Send `fee` of the deducted `amount` to the platform treasury, then move the rest to
+    /// the revenue pool, if one is configured, and notify the pool so it can attribute the
+    /// funds to this vault. With no revenue pool the rest stays in the vault as accrued
+    /// revenue.
+    fn settle(env: &Env, amount: i128, fee: i128) -> Result<(), VaultError> {
+        let usdc = token::Client::new(env, &Self::get_usdc(env)?);
+        let vault_address = env.current_contract_address();
+        if fee > 0 {
+            if let Some(platform_fee) = Self::get_platform_fee(env.clone()) {
+                usdc.transfer(&vault_address, &platform_fee.treasury, &fee);
+            }
+            let collected = Self::get_fees_collected(env.clone()) + fee;
+            env.storage()
+                .instance()
+                .set(&Symbol::new(env, FEES_COLLECTED_KEY), &collected);
+        }
+        let revenue = amount - fee;
+
+        let revenue_pool = match Self::get_revenue_pool(env.clone()) {
+            Some(pool) => pool,
+            None => {
+                let accrued = Self::get_accrued_revenue(env.clone()) + revenue;
+                env.storage()
+                    .instance()
+                    .set(&Symbol::new(env, ACCRUED_REVENUE_KEY), &accrued);
+                return Ok(());
+            }
+        };
+        if revenue > 0 {
+            usdc.transfer(&vault_address, &revenue_pool, &revenue);
+            RevenuePoolClient::new(env, &revenue_pool).receive_payment(
+                &vault_address,
+                &revenue,
+                &true,
+            );
+        }
+        Ok(())
     }
 }
 
@@ -1,7 +1,8 @@
 extern crate std;
 
 use super::*;
-use soroban_sdk::testutils::{Address as _, Events as _};
+use soroban_sdk::testutils::storage::{Instance as _, Persistent as _};
+use soroban_sdk::testutils::{Address as _, Events as _, Ledger as _};
 use soroban_sdk::{token, IntoVal, Symbol};
 
 fn create_usdc<'a>(
@@ -21,6 +22,12 @@ fn create_vault(env: &Env) -> (Address, CalloraVaultClient<'_>) {
     (address, client)
 }
 
+fn create_pool(env: &Env) -> (Address, callora_revenue_pool::RevenuePoolClient<'_>) {
+    let address = env.register(callora_revenue_pool::RevenuePool, ());
+    let client = callora_revenue_pool::RevenuePoolClient::new(env, &address);
+    (address, client)
+}
+
 fn fund_vault(
     usdc_admin_client: &token::StellarAssetClient,
     vault_address: &Address,
@@ -29,8 +36,26 @@ fn fund_vault(
     usdc_admin_client.mint(vault_address, &amount);
 }
 
+/// Mint `amount` to `depositor` and approve the vault to pull it via `deposit`.
+fn fund_depositor(
+    env: &Env,
+    usdc_admin_client: &token::StellarAssetClient,
+    usdc_client: &token::Client,
+    depositor: &Address,
+    vault_address: &Address,
+    amount: i128,
+) {
+    usdc_admin_client.mint(depositor, &amount);
+    usdc_client.approve(
+        depositor,
+        vault_address,
+        &amount,
+        &(env.ledger().sequence() + 1_000),
+    );
+}
+
 /// Full vault lifecycle integration test: init → deposit → batch_deduct →
-/// set_admin → withdraw_to, verifying state at each step.
+/// admin handover → withdraw_to, verifying state at each step.
 #[test]
 fn vault_full_lifecycle() {
     let env = Env::default();
@@ -38,21 +63,23 @@ fn vault_full_lifecycle() {
     let new_admin = Address::generate(&env);
     let caller = Address::generate(&env);
     let recipient = Address::generate(&env);
-    let contract_id = env.register(CalloraVault {}, ());
-    let client = CalloraVaultClient::new(&env, &contract_id);
-    let (usdc, _, _) = create_usdc(&env, &owner);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
 
     env.mock_all_auths();
 
     // 1. Initialise with 500 balance and min_deposit of 10.
-    let meta = client.init(&owner, &usdc, &Some(500), &Some(10));
+    fund_vault(&usdc_admin, &contract_id, 500);
+    let meta = client.init(&owner, &usdc, &Some(500), &Some(10), &None, &None);
+    client.add_deducter(&owner, &caller);
     assert_eq!(meta.balance, 500);
     assert_eq!(meta.owner, owner);
     assert_eq!(client.balance(), 500);
     assert_eq!(client.get_admin(), owner);
 
     // 2. Deposit – must be ≥ min_deposit.
-    let after_deposit = client.deposit(&200);
+    fund_depositor(&env, &usdc_admin, &usdc_client, &owner, &contract_id, 200);
+    let after_deposit = client.deposit(&owner, &200);
     assert_eq!(after_deposit, 700);
     assert_eq!(client.balance(), 700);
 
@@ -62,14 +89,17 @@ fn vault_full_lifecycle() {
         DeductItem {
             amount: 100,
             request_id: Some(Symbol::new(&env, "r1")),
+            depositor: None,
         },
         DeductItem {
             amount: 50,
             request_id: None,
+            depositor: None,
         },
         DeductItem {
             amount: 25,
             request_id: Some(Symbol::new(&env, "r3")),
+            depositor: None,
         },
     ];
     let after_batch = client.batch_deduct(&caller, &items);
@@ -81,13 +111,15 @@ fn vault_full_lifecycle() {
     assert_eq!(after_deduct, 500);
 
     // 5. Transfer admin to new_admin, then verify.
-    client.set_admin(&owner, &new_admin);
+    client.propose_admin(&owner, &new_admin, &100);
+    client.accept_admin();
     assert_eq!(client.get_admin(), new_admin);
 
     // 6. Withdraw to a recipient address.
     let after_withdraw = client.withdraw_to(&recipient, &100);
     assert_eq!(after_withdraw, 400);
     assert_eq!(client.balance(), 400);
+    assert_eq!(usdc_client.balance(&recipient), 100);
 
     // 7. Direct withdraw (to owner).
     let after_withdraw2 = client.withdraw(&50);
@@ -111,11 +143,11 @@ fn init_with_balance_emits_event() {
     let env = Env::default();
     let owner = Address::generate(&env);
     let contract_id = env.register(CalloraVault {}, ());
-
-    let usdc_token = Address::generate(&env);
+    let (usdc_token, _, usdc_admin) = create_usdc(&env, &owner);
 
     // Mock all auth checks so init can proceed without signatures
     env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 1000);
 
     // Invoke init inside as_contract so the SDK captures the published event.
     let events = env.as_contract(&contract_id, || {
@@ -125,7 +157,10 @@ fn init_with_balance_emits_event() {
             usdc_token.clone(),
             Some(1000),
             None,
-        );
+            None,
+            None,
+        )
+        .unwrap();
         env.events().all()
     });
 
@@ -165,10 +200,61 @@ fn init_defaults_balance_to_zero() {
     env.mock_all_auths();
 
     // Pass None — exercises the `unwrap_or(0)` branch in lib.rs.
-    client.init(&owner, &usdc_token, &None, &None);
+    client.init(&owner, &usdc_token, &None, &None, &None, &None);
     assert_eq!(client.balance(), 0);
 }
 
+/// init persists the revenue pool and max_deduct so the getters return them.
+#[test]
+fn init_stores_revenue_pool_and_max_deduct() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let pool = Address::generate(&env);
+    let (_, client) = create_vault(&env);
+    let usdc_token = Address::generate(&env);
+
+    env.mock_all_auths();
+    client.init(
+        &owner,
+        &usdc_token,
+        &None,
+        &None,
+        &Some(pool.clone()),
+        &Some(250),
+    );
+
+    assert_eq!(client.get_revenue_pool(), Some(pool));
+    assert_eq!(client.get_max_deduct(), 250);
+}
+
+/// Without explicit values the revenue pool is unset and max_deduct is uncapped.
+#[test]
+fn init_defaults_revenue_pool_and_max_deduct() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let (_, client) = create_vault(&env);
+    let usdc_token = Address::generate(&env);
+
+    env.mock_all_auths();
+    client.init(&owner, &usdc_token, &None, &None, &None, &None);
+
+    assert_eq!(client.get_revenue_pool(), None);
+    assert_eq!(client.get_max_deduct(), DEFAULT_MAX_DEDUCT);
+}
+
+/// init rejects an initial balance the contract does not actually hold.
+#[test]
+fn init_without_backing_usdc_fails() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let (_, client) = create_vault(&env);
+    let (usdc, _, _) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    let result = client.try_init(&owner, &usdc, &Some(100), &None, &None, &None);
+    assert_eq!(result, Err(Ok(VaultError::InsufficientUsdc)));
+}
+
 // ---------------------------------------------------------------------------
 // get_meta
 // ---------------------------------------------------------------------------
@@ -178,22 +264,21 @@ fn init_defaults_balance_to_zero() {
 fn get_meta_returns_owner_and_balance() {
     let env = Env::default();
     let owner = Address::generate(&env);
-    let contract_id = env.register(CalloraVault {}, ());
-    let client = CalloraVaultClient::new(&env, &contract_id);
-
-    let usdc_token = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
 
     env.mock_all_auths();
 
-    client.init(&owner, &usdc_token, &Some(500), &None);
+    fund_vault(&usdc_admin, &contract_id, 500);
+    client.init(&owner, &usdc, &Some(500), &None, &None, &None);
     let meta = client.get_meta();
 
     assert_eq!(meta.owner, owner);
     assert_eq!(meta.balance, 500);
 }
 
-/// Calling get_meta before init must return an error (not a panic that kills
-/// the test process) — exercises the `unwrap_or_else(panic)` error path.
+/// Calling get_meta before init must return `NotInitialized` rather than a
+/// panic that kills the test process.
 #[test]
 fn get_meta_before_init_fails() {
     let env = Env::default();
@@ -202,10 +287,7 @@ fn get_meta_before_init_fails() {
 
     // try_get_meta() is the Result-returning variant generated by the SDK.
     let result = client.try_get_meta();
-    assert!(
-        result.is_err(),
-        "expected error when vault is uninitialised"
-    );
+    assert_eq!(result, Err(Ok(VaultError::NotInitialized)));
 }
 
 // ---------------------------------------------------------------------------
@@ -217,21 +299,160 @@ fn get_meta_before_init_fails() {
 fn deposit_and_balance_match() {
     let env = Env::default();
     let owner = Address::generate(&env);
-    let contract_id = env.register(CalloraVault {}, ());
-    let client = CalloraVaultClient::new(&env, &contract_id);
-
-    let usdc_token = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
 
     env.mock_all_auths();
 
-    client.init(&owner, &usdc_token, &Some(100), &None);
-    let returned = client.deposit(&200);
+    fund_vault(&usdc_admin, &contract_id, 100);
+    client.init(&owner, &usdc, &Some(100), &None, &None, &None);
+    fund_depositor(&env, &usdc_admin, &usdc_client, &owner, &contract_id, 200);
+    let returned = client.deposit(&owner, &200);
 
     assert_eq!(
         returned, 300,
         "deposit should return the new running balance"
     );
     assert_eq!(client.balance(), 300);
+    assert_eq!(usdc_client.balance(&contract_id), 300);
+}
+
+// ---------------------------------------------------------------------------
+// depositor sub-accounts
+// ---------------------------------------------------------------------------
+
+/// Deposits land in each depositor's sub-account and deduct_from charges only the named one.
+#[test]
+fn deduct_from_charges_named_sub_account() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 100);
+    client.init(&owner, &usdc, &Some(100), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+    fund_depositor(&env, &usdc_admin, &usdc_client, &alice, &contract_id, 300);
+    fund_depositor(&env, &usdc_admin, &usdc_client, &bob, &contract_id, 200);
+    client.deposit(&alice, &300);
+    client.deposit(&bob, &200);
+
+    assert_eq!(client.balance_of(&owner), 100);
+    assert_eq!(client.balance_of(&alice), 300);
+    assert_eq!(client.balance_of(&bob), 200);
+    assert_eq!(client.balance(), 600);
+
+    let remaining = client.deduct_from(&caller, &alice, &120, &None);
+    let events = env.events().all();
+    let last_event = events.last().expect("expected deduct event");
+    let topic3: Address = last_event.1.get(3).unwrap().into_val(&env);
+    assert_eq!(topic3, alice);
+    assert_eq!(remaining, 480);
+    assert_eq!(client.balance_of(&alice), 180);
+    assert_eq!(client.balance_of(&bob), 200);
+
+    // Plain deduct draws on the owner's sub-account.
+    client.deduct(&caller, &40, &None);
+    assert_eq!(client.balance_of(&owner), 60);
+
+    // Bob cannot be charged beyond his own funds even though the vault holds more.
+    assert_eq!(
+        client.try_deduct_from(&caller, &bob, &201, &None),
+        Err(Ok(VaultError::InsufficientBalance))
+    );
+}
+
+/// batch_deduct charges each item to its depositor and validates sub-accounts cumulatively.
+#[test]
+fn batch_deduct_charges_each_items_depositor() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 100);
+    client.init(&owner, &usdc, &Some(100), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+    fund_depositor(&env, &usdc_admin, &usdc_client, &alice, &contract_id, 50);
+    client.deposit(&alice, &50);
+
+    let items = soroban_sdk::vec![
+        &env,
+        DeductItem {
+            amount: 30,
+            request_id: None,
+            depositor: Some(alice.clone()),
+        },
+        DeductItem {
+            amount: 30,
+            request_id: None,
+            depositor: Some(alice.clone()),
+        },
+    ];
+    assert_eq!(
+        client.try_batch_deduct(&caller, &items),
+        Err(Ok(VaultError::InsufficientBalance))
+    );
+
+    let items = soroban_sdk::vec![
+        &env,
+        DeductItem {
+            amount: 30,
+            request_id: None,
+            depositor: Some(alice.clone()),
+        },
+        DeductItem {
+            amount: 25,
+            request_id: None,
+            depositor: None,
+        },
+    ];
+    assert_eq!(client.batch_deduct(&caller, &items), 95);
+    assert_eq!(client.balance_of(&alice), 20);
+    assert_eq!(client.balance_of(&owner), 75);
+}
+
+/// Each depositor withdraws their own funds; the owner cannot reach other sub-accounts.
+#[test]
+fn withdraw_deposit_returns_own_funds_only() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 100);
+    client.init(&owner, &usdc, &Some(100), &None, &None, &None);
+    fund_depositor(&env, &usdc_admin, &usdc_client, &alice, &contract_id, 200);
+    client.deposit(&alice, &200);
+
+    assert_eq!(
+        client.try_withdraw(&150),
+        Err(Ok(VaultError::InsufficientBalance))
+    );
+    assert_eq!(
+        client.try_withdraw_deposit(&alice, &201),
+        Err(Ok(VaultError::InsufficientBalance))
+    );
+
+    let remaining = client.withdraw_deposit(&alice, &200);
+    let events = env.events().all();
+    let last_event = events.last().expect("expected withdraw event");
+    let topic0: Symbol = last_event.1.get(0).unwrap().into_val(&env);
+    let topic1: Address = last_event.1.get(1).unwrap().into_val(&env);
+    assert_eq!(topic0, Symbol::new(&env, "withdraw"));
+    assert_eq!(topic1, alice);
+    assert_eq!(remaining, 100);
+    assert_eq!(client.balance_of(&alice), 0);
+    assert_eq!(usdc_client.balance(&alice), 200);
 }
 
 // ---------------------------------------------------------------------------
@@ -243,15 +464,17 @@ fn deposit_and_balance_match() {
 fn deduct_reduces_balance() {
     let env = Env::default();
     let owner = Address::generate(&env);
-    let contract_id = env.register(CalloraVault {}, ());
-    let client = CalloraVaultClient::new(&env, &contract_id);
+    let (contract_id, client) = create_vault(&env);
 
-    let (usdc, _, _) = create_usdc(&env, &owner);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
     let caller = Address::generate(&env);
 
     env.mock_all_auths();
-    client.init(&owner, &usdc, &Some(100), &None);
-    client.deposit(&200);
+    fund_vault(&usdc_admin, &contract_id, 100);
+    client.init(&owner, &usdc, &Some(100), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+    fund_depositor(&env, &usdc_admin, &usdc_client, &owner, &contract_id, 200);
+    client.deposit(&owner, &200);
     assert_eq!(client.balance(), 300);
 
     let returned = client.deduct(&caller, &50, &None);
@@ -264,40 +487,42 @@ fn deduct_reduces_balance() {
 fn deduct_with_request_id() {
     let env = Env::default();
     let owner = Address::generate(&env);
-    let contract_id = env.register(CalloraVault {}, ());
-    let client = CalloraVaultClient::new(&env, &contract_id);
+    let (contract_id, client) = create_vault(&env);
 
-    let (usdc, _, _) = create_usdc(&env, &owner);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
     let caller = Address::generate(&env);
 
     env.mock_all_auths();
-    client.init(&owner, &usdc, &Some(1000), &None);
+    fund_vault(&usdc_admin, &contract_id, 1000);
+    client.init(&owner, &usdc, &Some(1000), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
 
     let request_id = Symbol::new(&env, "req123");
     let remaining = client.deduct(&caller, &100, &Some(request_id));
     assert_eq!(remaining, 900);
 }
 
-/// Deducting more than the available balance must be rejected — exercises the
-/// `assert!(meta.balance >= amount, "insufficient balance")` guard.
+/// Deducting more than the available balance must be rejected with
+/// `InsufficientBalance`.
 #[test]
 fn deduct_insufficient_balance_fails() {
     let env = Env::default();
     let owner = Address::generate(&env);
-    let contract_id = env.register(CalloraVault {}, ());
-    let client = CalloraVaultClient::new(&env, &contract_id);
+    let (contract_id, client) = create_vault(&env);
 
-    let usdc_token = Address::generate(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
     let caller = Address::generate(&env);
 
     env.mock_all_auths();
 
-    client.init(&owner, &usdc_token, &Some(10), &None);
+    fund_vault(&usdc_admin, &contract_id, 10);
+    client.init(&owner, &usdc, &Some(10), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
 
     // try_deduct() returns Result so we can assert on the error without
     // unwinding the test runner.
     let result = client.try_deduct(&caller, &100, &None);
-    assert!(result.is_err(), "expected error for insufficient balance");
+    assert_eq!(result, Err(Ok(VaultError::InsufficientBalance)));
 }
 
 /// Deducting exactly the full balance should succeed and leave zero.
@@ -305,280 +530,1915 @@ fn deduct_insufficient_balance_fails() {
 fn deduct_exact_balance_succeeds() {
     let env = Env::default();
     let owner = Address::generate(&env);
-    let contract_id = env.register(CalloraVault {}, ());
-    let client = CalloraVaultClient::new(&env, &contract_id);
+    let (contract_id, client) = create_vault(&env);
 
-    let usdc_token = Address::generate(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
     let caller = Address::generate(&env);
 
     env.mock_all_auths();
 
-    client.init(&owner, &usdc_token, &Some(75), &None);
+    fund_vault(&usdc_admin, &contract_id, 75);
+    client.init(&owner, &usdc, &Some(75), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
     let remaining = client.deduct(&caller, &75, &None);
 
     assert_eq!(remaining, 0);
     assert_eq!(client.balance(), 0);
 }
 
-// ---------------------------------------------------------------------------
-// admin management
-// ---------------------------------------------------------------------------
-
-/// get_admin returns the admin address set during init.
+/// Deducting more than max_deduct is rejected.
 #[test]
-fn get_admin_returns_correct_address() {
+fn deduct_above_max_deduct_fails() {
     let env = Env::default();
     let owner = Address::generate(&env);
-    let contract_id = env.register(CalloraVault {}, ());
-    let client = CalloraVaultClient::new(&env, &contract_id);
-    let usdc_token = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
 
     env.mock_all_auths();
-    client.init(&owner, &usdc_token, &Some(100), &None);
+    fund_vault(&usdc_admin, &contract_id, 1000);
+    client.init(&owner, &usdc, &Some(1000), &None, &None, &Some(100));
+    client.add_deducter(&owner, &caller);
 
-    let admin = client.get_admin();
-    assert_eq!(admin, owner);
+    let result = client.try_deduct(&caller, &101, &None);
+    assert_eq!(result, Err(Ok(VaultError::ExceedsMaxDeduct)));
+    assert_eq!(client.balance(), 1000);
 }
 
-/// Only the current admin can update the admin address.
+// ---------------------------------------------------------------------------
+// revenue pool settlement
+// ---------------------------------------------------------------------------
+
+/// Without a revenue pool, deducted USDC stays in the vault.
 #[test]
-fn set_admin_updates_admin() {
+fn deduct_without_revenue_pool_keeps_usdc_in_vault() {
     let env = Env::default();
     let owner = Address::generate(&env);
-    let new_admin = Address::generate(&env);
-    let contract_id = env.register(CalloraVault {}, ());
-    let client = CalloraVaultClient::new(&env, &contract_id);
-    let usdc_token = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (vault_address, client) = create_vault(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
 
     env.mock_all_auths();
-    client.init(&owner, &usdc_token, &Some(100), &None);
+    fund_vault(&usdc_admin, &vault_address, 500);
+    client.init(&owner, &usdc, &Some(500), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
 
-    client.set_admin(&owner, &new_admin);
-    assert_eq!(client.get_admin(), new_admin);
+    client.deduct(&caller, &200, &None);
+    assert_eq!(client.balance(), 300);
+    assert_eq!(usdc_client.balance(&vault_address), 500);
 }
 
-/// Non-admin callers cannot change the admin.
+/// Each deduct transfers the deducted USDC to the revenue pool and notifies it.
 #[test]
-fn set_admin_unauthorized_fails() {
+fn deduct_settles_usdc_into_revenue_pool() {
     let env = Env::default();
     let owner = Address::generate(&env);
-    let intruder = Address::generate(&env);
-    let new_admin = Address::generate(&env);
-    let contract_id = env.register(CalloraVault {}, ());
-    let client = CalloraVaultClient::new(&env, &contract_id);
-    let usdc_token = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (vault_address, client) = create_vault(&env);
+    let (pool_address, pool_client) = create_pool(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
 
     env.mock_all_auths();
-    client.init(&owner, &usdc_token, &Some(100), &None);
-
-    let result = client.try_set_admin(&intruder, &new_admin);
-    assert!(
-        result.is_err(),
-        "expected error when non-admin tries to set admin"
+    pool_client.init(&owner, &usdc);
+    pool_client.add_vault(&owner, &vault_address);
+    fund_vault(&usdc_admin, &vault_address, 500);
+    client.init(
+        &owner,
+        &usdc,
+        &Some(500),
+        &None,
+        &Some(pool_address.clone()),
+        &None,
     );
+    client.add_deducter(&owner, &caller);
+
+    client.deduct(&caller, &120, &Some(Symbol::new(&env, "req1")));
+
+    // The pool's receive_payment event attributes the funds to the vault.
+    let pool_event = env
+        .events()
+        .all()
+        .iter()
+        .find(|e| {
+            e.0 == pool_address && {
+                let topic0: Symbol = e.1.get(0).unwrap().into_val(&env);
+                topic0 == Symbol::new(&env, "receive_payment")
+            }
+        })
+        .expect("expected receive_payment event from pool");
+    let source: Address = pool_event.1.get(1).unwrap().into_val(&env);
+    assert_eq!(source, vault_address);
+    let data: (i128, bool) = pool_event.2.into_val(&env);
+    assert_eq!(data, (120, true));
+
+    assert_eq!(client.balance(), 380);
+    assert_eq!(usdc_client.balance(&vault_address), 380);
+    assert_eq!(usdc_client.balance(&pool_address), 120);
+    assert_eq!(pool_client.balance(), 120);
 }
 
-// ---------------------------------------------------------------------------
-// distribute
-// ---------------------------------------------------------------------------
-
-/// Admin can distribute USDC from vault to a developer.
+/// A batch deduct settles the aggregate amount in a single transfer.
 #[test]
-fn distribute_transfers_usdc_to_developer() {
+fn batch_deduct_settles_total_into_revenue_pool() {
     let env = Env::default();
-    let admin = Address::generate(&env);
-    let developer = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
     let (vault_address, client) = create_vault(&env);
-    let (usdc, _, usdc_admin_client) = create_usdc(&env, &admin);
+    let (pool_address, pool_client) = create_pool(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
 
     env.mock_all_auths();
+    pool_client.init(&owner, &usdc);
+    pool_client.add_vault(&owner, &vault_address);
+    fund_vault(&usdc_admin, &vault_address, 1000);
+    client.init(
+        &owner,
+        &usdc,
+        &Some(1000),
+        &None,
+        &Some(pool_address.clone()),
+        &None,
+    );
+    client.add_deducter(&owner, &caller);
 
-    client.init(&admin, &usdc, &Some(0), &None);
-
-    // Mint 1000 USDC into the vault
-    fund_vault(&env, &usdc_admin_client, &vault_address, 1000);
-
-    // Distribute 300 to developer
-    client.distribute(&admin, &developer, &300);
+    let items = soroban_sdk::vec![
+        &env,
+        DeductItem {
+            amount: 100,
+            request_id: None,
+            depositor: None,
+        },
+        DeductItem {
+            amount: 250,
+            request_id: None,
+            depositor: None,
+        }
+    ];
+    client.batch_deduct(&caller, &items);
+
+    let receive_events = env
+        .events()
+        .all()
+        .iter()
+        .filter(|e| {
+            e.0 == pool_address && {
+                let topic0: Symbol = e.1.get(0).unwrap().into_val(&env);
+                topic0 == Symbol::new(&env, "receive_payment")
+            }
+        })
+        .count();
+    assert_eq!(receive_events, 1);
 
-    // Verify developer received the funds
-    let usdc_client = token::Client::new(&env, &usdc);
-    assert_eq!(usdc_client.balance(&developer), 300);
-    assert_eq!(usdc_client.balance(&vault_address), 700);
+    assert_eq!(client.balance(), 650);
+    assert_eq!(usdc_client.balance(&vault_address), 650);
+    assert_eq!(usdc_client.balance(&pool_address), 350);
 }
 
-/// Non-admin cannot distribute funds.
+// ---------------------------------------------------------------------------
+// platform fee
+// ---------------------------------------------------------------------------
+
+/// Each deduct sends the fee (rounded down) to the treasury and the rest to the pool.
 #[test]
-fn distribute_unauthorized_fails() {
+fn platform_fee_split_between_treasury_and_pool() {
     let env = Env::default();
-    let admin = Address::generate(&env);
-    let intruder = Address::generate(&env);
-    let developer = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let treasury = Address::generate(&env);
     let (vault_address, client) = create_vault(&env);
-    let (usdc, _, usdc_admin_client) = create_usdc(&env, &admin);
+    let (pool_address, pool_client) = create_pool(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
 
     env.mock_all_auths();
-
-    client.init(&admin, &usdc, &Some(0), &None);
-    fund_vault(&env, &usdc_admin_client, &vault_address, 1000);
-
-    let result = client.try_distribute(&intruder, &developer, &300);
-    assert!(
-        result.is_err(),
-        "expected error when non-admin tries to distribute"
+    pool_client.init(&owner, &usdc);
+    pool_client.add_vault(&owner, &vault_address);
+    fund_vault(&usdc_admin, &vault_address, 1000);
+    client.init(
+        &owner,
+        &usdc,
+        &Some(1000),
+        &None,
+        &Some(pool_address.clone()),
+        &None,
     );
+    client.add_deducter(&owner, &caller);
+    let fee = PlatformFee {
+        fee_bps: 250,
+        treasury: treasury.clone(),
+    };
+    client.set_platform_fee(&owner, &Some(fee.clone()));
+    assert_eq!(client.get_platform_fee(), Some(fee));
+
+    // 2.5% of 199 is 4.975, rounded down to 4.
+    client.deduct(&caller, &199, &Some(Symbol::new(&env, "req1")));
+    let events = env.events().all();
+    let last_event = events.last().expect("expected deduct event");
+    let data: (i128, i128, i128, i128) = last_event.2.into_val(&env);
+    assert_eq!(data, (199, 801, 4, 195));
+    assert_eq!(usdc_client.balance(&treasury), 4);
+    assert_eq!(usdc_client.balance(&pool_address), 195);
+
+    // Batch fees are rounded per item: 4 + 2 instead of 6.
+    let items = soroban_sdk::vec![
+        &env,
+        DeductItem {
+            amount: 199,
+            request_id: None,
+            depositor: None,
+        },
+        DeductItem {
+            amount: 101,
+            request_id: None,
+            depositor: None,
+        }
+    ];
+    client.batch_deduct(&caller, &items);
+    assert_eq!(usdc_client.balance(&treasury), 10);
+    assert_eq!(usdc_client.balance(&pool_address), 489);
+    assert_eq!(usdc_client.balance(&vault_address), 501);
+    assert_eq!(client.get_fees_collected(), 10);
 }
 
-/// Distributing more than vault balance fails.
+/// Without a pool the fee still goes to the treasury; only the remainder accrues.
 #[test]
-fn distribute_insufficient_usdc_fails() {
+fn platform_fee_without_pool_and_validation() {
     let env = Env::default();
-    let admin = Address::generate(&env);
-    let developer = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let treasury = Address::generate(&env);
     let (vault_address, client) = create_vault(&env);
-    let (usdc, _, usdc_admin_client) = create_usdc(&env, &admin);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
 
     env.mock_all_auths();
+    fund_vault(&usdc_admin, &vault_address, 1000);
+    client.init(&owner, &usdc, &Some(1000), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+
+    let too_high = PlatformFee {
+        fee_bps: 10_001,
+        treasury: treasury.clone(),
+    };
+    assert_eq!(
+        client.try_set_platform_fee(&owner, &Some(too_high)),
+        Err(Ok(VaultError::InvalidConfig))
+    );
+    let fee = PlatformFee {
+        fee_bps: 1_000,
+        treasury: treasury.clone(),
+    };
+    assert_eq!(
+        client.try_set_platform_fee(&caller, &Some(fee.clone())),
+        Err(Ok(VaultError::Unauthorized))
+    );
+    client.set_platform_fee(&owner, &Some(fee));
 
-    client.init(&admin, &usdc, &Some(0), &None);
-    fund_vault(&env, &usdc_admin_client, &vault_address, 100);
+    client.deduct(&caller, &300, &None);
+    assert_eq!(usdc_client.balance(&treasury), 30);
+    assert_eq!(client.get_accrued_revenue(), 270);
+    assert_eq!(client.get_fees_collected(), 30);
 
-    let result = client.try_distribute(&admin, &developer, &500);
-    assert!(
-        result.is_err(),
-        "expected error for insufficient USDC balance"
-    );
+    client.set_platform_fee(&owner, &None);
+    client.deduct(&caller, &100, &None);
+    assert_eq!(usdc_client.balance(&treasury), 30);
+    assert_eq!(client.get_accrued_revenue(), 370);
 }
 
-/// Distributing zero or negative amount fails.
+/// Refunds and disputes cover only the revenue part of a deduct; the fee stays with the
+/// treasury instead of being taken out of developer revenue.
 #[test]
-fn distribute_zero_amount_fails() {
+fn refund_and_dispute_exclude_platform_fee() {
     let env = Env::default();
-    let admin = Address::generate(&env);
-    let developer = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let treasury = Address::generate(&env);
     let (vault_address, client) = create_vault(&env);
-    let (usdc, _, usdc_admin_client) = create_usdc(&env, &admin);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
 
     env.mock_all_auths();
+    fund_vault(&usdc_admin, &vault_address, 1000);
+    client.init(&owner, &usdc, &Some(1000), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+    client.set_platform_fee(
+        &owner,
+        &Some(PlatformFee {
+            fee_bps: 1_000,
+            treasury: treasury.clone(),
+        }),
+    );
 
-    client.init(&admin, &usdc, &Some(0), &None);
-    fund_vault(&env, &usdc_admin_client, &vault_address, 1000);
-
-    let result = client.try_distribute(&admin, &developer, &0);
-    assert!(result.is_err(), "expected error for zero amount");
+    let req1 = Symbol::new(&env, "req1");
+    client.deduct(&caller, &300, &Some(req1.clone()));
+    assert_eq!(client.get_deduct_record(&req1).unwrap().fee, 30);
+    assert_eq!(
+        client.try_refund(&caller, &req1, &300),
+        Err(Ok(VaultError::ExceedsDeducted))
+    );
+    client.refund(&caller, &req1, &270);
+    assert_eq!(client.get_accrued_revenue(), 0);
+    assert_eq!(client.balance(), 970);
+
+    let req2 = Symbol::new(&env, "req2");
+    client.deduct(&caller, &100, &Some(req2.clone()));
+    let dispute = client.dispute(&req2, &1);
+    assert_eq!(dispute.amount, 90);
+    client.resolve_dispute(&owner, &req2, &10_000);
+    assert_eq!(client.get_accrued_revenue(), 0);
+    assert_eq!(client.balance(), 960);
+    assert_eq!(usdc_client.balance(&treasury), 40);
 }
 
 // ---------------------------------------------------------------------------
-// batch_deduct
+// deducter allowlist
 // ---------------------------------------------------------------------------
 
-/// Batch deduct processes multiple items and emits multiple events.
+/// Admin can add and remove deducters; is_deducter reflects the allowlist.
 #[test]
-fn batch_deduct_multiple_items() {
+fn add_and_remove_deducter() {
     let env = Env::default();
     let owner = Address::generate(&env);
-    let caller = Address::generate(&env);
-    let contract_id = env.register(CalloraVault {}, ());
-    let client = CalloraVaultClient::new(&env, &contract_id);
+    let operator = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
     let usdc_token = Address::generate(&env);
 
     env.mock_all_auths();
-    client.init(&owner, &usdc_token, &Some(1000), &None);
-
-    let items = soroban_sdk::vec![
-        &env,
-        DeductItem {
-            amount: 100,
-            request_id: Some(Symbol::new(&env, "req1"))
-        },
-        DeductItem {
-            amount: 200,
-            request_id: None
-        },
-        DeductItem {
-            amount: 50,
-            request_id: Some(Symbol::new(&env, "req2"))
-        }
-    ];
+    client.init(&owner, &usdc_token, &None, &None, &None, &None);
+    assert!(!client.is_deducter(&operator));
 
-    let remaining = client.batch_deduct(&caller, &items);
-    assert_eq!(remaining, 650); // 1000 - 100 - 200 - 50
-    assert_eq!(client.balance(), 650);
+    client.add_deducter(&owner, &operator);
+    let events = env.events().all();
+    let last_event = events.last().expect("expected deducter_added event");
+    assert_eq!(last_event.0, contract_id);
+    let topic0: Symbol = last_event.1.get(0).unwrap().into_val(&env);
+    let topic1: Address = last_event.1.get(1).unwrap().into_val(&env);
+    assert_eq!(topic0, Symbol::new(&env, "deducter_added"));
+    assert_eq!(topic1, operator);
+    assert!(client.is_deducter(&operator));
+
+    client.remove_deducter(&owner, &operator);
+    let events = env.events().all();
+    let last_event = events.last().expect("expected deducter_removed event");
+    let topic0: Symbol = last_event.1.get(0).unwrap().into_val(&env);
+    assert_eq!(topic0, Symbol::new(&env, "deducter_removed"));
+    assert!(!client.is_deducter(&operator));
 }
 
-/// Batch deduct fails if any item would overdraw.
+/// Non-admin callers cannot manage the deducter allowlist.
 #[test]
-fn batch_deduct_insufficient_balance_fails() {
+fn add_deducter_unauthorized_fails() {
     let env = Env::default();
     let owner = Address::generate(&env);
-    let caller = Address::generate(&env);
+    let intruder = Address::generate(&env);
+    let (_, client) = create_vault(&env);
+    let usdc_token = Address::generate(&env);
+
+    env.mock_all_auths();
+    client.init(&owner, &usdc_token, &None, &None, &None, &None);
+
+    let result = client.try_add_deducter(&intruder, &intruder);
+    assert_eq!(result, Err(Ok(VaultError::Unauthorized)));
+    assert!(!client.is_deducter(&intruder));
+}
+
+/// Removing an address that is not on the allowlist fails.
+#[test]
+fn remove_unknown_deducter_fails() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let operator = Address::generate(&env);
+    let (_, client) = create_vault(&env);
+    let usdc_token = Address::generate(&env);
+
+    env.mock_all_auths();
+    client.init(&owner, &usdc_token, &None, &None, &None, &None);
+
+    let result = client.try_remove_deducter(&owner, &operator);
+    assert_eq!(result, Err(Ok(VaultError::DeducterNotFound)));
+}
+
+/// Callers not on the allowlist cannot deduct or batch deduct.
+#[test]
+fn deduct_by_unlisted_caller_fails() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 100);
+    client.init(&owner, &usdc, &Some(100), &None, &None, &None);
+
+    let result = client.try_deduct(&caller, &10, &None);
+    assert_eq!(result, Err(Ok(VaultError::Unauthorized)));
+
+    let items = soroban_sdk::vec![
+        &env,
+        DeductItem {
+            amount: 10,
+            request_id: None,
+            depositor: None,
+        }
+    ];
+    let result = client.try_batch_deduct(&caller, &items);
+    assert_eq!(result, Err(Ok(VaultError::Unauthorized)));
+    assert_eq!(client.balance(), 100);
+}
+
+/// A removed deducter loses the ability to deduct.
+#[test]
+fn removed_deducter_cannot_deduct() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 100);
+    client.init(&owner, &usdc, &Some(100), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+    client.deduct(&caller, &10, &None);
+
+    client.remove_deducter(&owner, &caller);
+    let result = client.try_deduct(&caller, &10, &None);
+    assert_eq!(result, Err(Ok(VaultError::Unauthorized)));
+    assert_eq!(client.balance(), 90);
+}
+
+// ---------------------------------------------------------------------------
+// request_id idempotency
+// ---------------------------------------------------------------------------
+
+/// A processed request_id is reported by is_processed and cannot be charged twice.
+#[test]
+fn deduct_duplicate_request_id_fails() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 500);
+    client.init(&owner, &usdc, &Some(500), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+
+    let rid = Symbol::new(&env, "req1");
+    assert!(!client.is_processed(&rid));
+    client.deduct(&caller, &100, &Some(rid.clone()));
+    assert!(client.is_processed(&rid));
+
+    let result = client.try_deduct(&caller, &100, &Some(rid.clone()));
+    assert_eq!(result, Err(Ok(VaultError::DuplicateRequest)));
+    assert_eq!(client.balance(), 400);
+
+    // Deducts without a request_id are never deduplicated.
+    client.deduct(&caller, &10, &None);
+    client.deduct(&caller, &10, &None);
+    assert_eq!(client.balance(), 380);
+}
+
+/// A batch repeating a request_id internally is rejected as a whole.
+#[test]
+fn batch_deduct_duplicate_within_batch_fails() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 500);
+    client.init(&owner, &usdc, &Some(500), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+
+    let items = soroban_sdk::vec![
+        &env,
+        DeductItem {
+            amount: 10,
+            request_id: Some(Symbol::new(&env, "dup")),
+            depositor: None,
+        },
+        DeductItem {
+            amount: 20,
+            request_id: Some(Symbol::new(&env, "dup")),
+            depositor: None,
+        }
+    ];
+    let result = client.try_batch_deduct(&caller, &items);
+    assert_eq!(result, Err(Ok(VaultError::DuplicateRequest)));
+    assert_eq!(client.balance(), 500);
+    assert!(!client.is_processed(&Symbol::new(&env, "dup")));
+}
+
+/// A batch containing an already-processed request_id is rejected, and every
+/// request_id of a successful batch is recorded.
+#[test]
+fn batch_deduct_replayed_request_id_fails() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 500);
+    client.init(&owner, &usdc, &Some(500), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+
+    let items = soroban_sdk::vec![
+        &env,
+        DeductItem {
+            amount: 10,
+            request_id: Some(Symbol::new(&env, "a")),
+            depositor: None,
+        },
+        DeductItem {
+            amount: 20,
+            request_id: Some(Symbol::new(&env, "b")),
+            depositor: None,
+        }
+    ];
+    client.batch_deduct(&caller, &items);
+    assert!(client.is_processed(&Symbol::new(&env, "a")));
+    assert!(client.is_processed(&Symbol::new(&env, "b")));
+
+    let retry = soroban_sdk::vec![
+        &env,
+        DeductItem {
+            amount: 5,
+            request_id: Some(Symbol::new(&env, "c")),
+            depositor: None,
+        },
+        DeductItem {
+            amount: 20,
+            request_id: Some(Symbol::new(&env, "b")),
+            depositor: None,
+        }
+    ];
+    let result = client.try_batch_deduct(&caller, &retry);
+    assert_eq!(result, Err(Ok(VaultError::DuplicateRequest)));
+    assert_eq!(client.balance(), 470);
+    assert!(!client.is_processed(&Symbol::new(&env, "c")));
+}
+
+/// Processed request ids expire after the configured retention TTL.
+#[test]
+fn request_id_expires_after_ttl() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 500);
+    client.init(&owner, &usdc, &Some(500), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+    assert_eq!(client.get_request_ttl(), DEFAULT_REQUEST_TTL);
+
+    client.set_request_ttl(&owner, &100);
+    assert_eq!(client.get_request_ttl(), 100);
+
+    let rid = Symbol::new(&env, "req1");
+    client.deduct(&caller, &10, &Some(rid.clone()));
+    assert!(client.is_processed(&rid));
+
+    env.ledger().with_mut(|li| li.sequence_number += 101);
+    assert!(!client.is_processed(&rid));
+}
+
+/// Only the admin may change the request retention TTL, and it must be positive.
+#[test]
+fn set_request_ttl_validation() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let intruder = Address::generate(&env);
+    let (_, client) = create_vault(&env);
+    let usdc_token = Address::generate(&env);
+
+    env.mock_all_auths();
+    client.init(&owner, &usdc_token, &None, &None, &None, &None);
+
+    assert_eq!(
+        client.try_set_request_ttl(&intruder, &100),
+        Err(Ok(VaultError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_set_request_ttl(&owner, &0),
+        Err(Ok(VaultError::InvalidConfig))
+    );
+    let max_ttl = env.as_contract(&client.address, || env.storage().max_ttl());
+    assert_eq!(
+        client.try_set_request_ttl(&owner, &(max_ttl + 1)),
+        Err(Ok(VaultError::InvalidConfig))
+    );
+    assert_eq!(client.get_request_ttl(), DEFAULT_REQUEST_TTL);
+    client.set_request_ttl(&owner, &max_ttl);
+}
+
+// ---------------------------------------------------------------------------
+// pause
+// ---------------------------------------------------------------------------
+
+/// Each operation can be paused independently; unpausing restores it.
+#[test]
+fn pause_freezes_only_selected_operations() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let developer = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 500);
+    client.init(&owner, &usdc, &Some(500), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+
+    let ops = PauseState {
+        deduct: true,
+        distribute: true,
+        ..PauseState::default()
+    };
+    let state = client.pause(&owner, &ops);
+    assert_eq!(state, ops);
+    assert_eq!(client.get_pause_state(), ops);
+
+    assert_eq!(
+        client.try_deduct(&caller, &10, &None),
+        Err(Ok(VaultError::Paused))
+    );
+    assert_eq!(
+        client.try_distribute(&owner, &developer, &10),
+        Err(Ok(VaultError::Paused))
+    );
+
+    // batch_deduct and deposit are not part of the paused set.
+    let items = soroban_sdk::vec![
+        &env,
+        DeductItem {
+            amount: 10,
+            request_id: None,
+            depositor: None,
+        }
+    ];
+    client.batch_deduct(&caller, &items);
+    fund_depositor(&env, &usdc_admin, &usdc_client, &owner, &contract_id, 50);
+    client.deposit(&owner, &50);
+    assert_eq!(client.balance(), 540);
+
+    let state = client.unpause(
+        &owner,
+        &PauseState {
+            deduct: true,
+            ..PauseState::default()
+        },
+    );
+    assert!(!state.deduct);
+    assert!(state.distribute);
+    client.deduct(&caller, &10, &None);
+    assert_eq!(client.balance(), 530);
+}
+
+/// Pausing deposits and batch deducts rejects those calls.
+#[test]
+fn pause_deposit_and_batch_deduct() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 100);
+    client.init(&owner, &usdc, &Some(100), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+    client.pause(
+        &owner,
+        &PauseState {
+            deposit: true,
+            batch_deduct: true,
+            ..PauseState::default()
+        },
+    );
+
+    fund_depositor(&env, &usdc_admin, &usdc_client, &owner, &contract_id, 50);
+    assert_eq!(client.try_deposit(&owner, &50), Err(Ok(VaultError::Paused)));
+    let items = soroban_sdk::vec![
+        &env,
+        DeductItem {
+            amount: 10,
+            request_id: None,
+            depositor: None,
+        }
+    ];
+    assert_eq!(
+        client.try_batch_deduct(&caller, &items),
+        Err(Ok(VaultError::Paused))
+    );
+    assert_eq!(client.balance(), 100);
+}
+
+/// Owner withdrawals keep working while other operations are paused and are
+/// only frozen when the admin explicitly pauses `withdraw`.
+#[test]
+fn withdraw_allowed_during_pause_unless_explicitly_paused() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 500);
+    client.init(&owner, &usdc, &Some(500), &None, &None, &None);
+
+    client.pause(
+        &owner,
+        &PauseState {
+            deposit: true,
+            deduct: true,
+            batch_deduct: true,
+            distribute: true,
+            withdraw: false,
+        },
+    );
+    client.withdraw(&100);
+    client.withdraw_to(&recipient, &100);
+    assert_eq!(client.balance(), 300);
+
+    client.pause(
+        &owner,
+        &PauseState {
+            withdraw: true,
+            ..PauseState::default()
+        },
+    );
+    assert_eq!(client.try_withdraw(&100), Err(Ok(VaultError::Paused)));
+    assert_eq!(
+        client.try_withdraw_to(&recipient, &100),
+        Err(Ok(VaultError::Paused))
+    );
+}
+
+/// The guardian may pause operations (except withdraw) but never unpause.
+#[test]
+fn guardian_can_only_pause() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let guardian = Address::generate(&env);
+    let intruder = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let usdc_token = Address::generate(&env);
+
+    env.mock_all_auths();
+    client.init(&owner, &usdc_token, &None, &None, &None, &None);
+    client.set_guardian(&owner, &Some(guardian.clone()));
+    assert_eq!(client.get_guardian(), Some(guardian.clone()));
+
+    let ops = PauseState {
+        deduct: true,
+        ..PauseState::default()
+    };
+    assert_eq!(
+        client.try_pause(&intruder, &ops),
+        Err(Ok(VaultError::Unauthorized))
+    );
+    client.pause(&guardian, &ops);
+
+    let events = env.events().all();
+    let last_event = events.last().expect("expected pause event");
+    assert_eq!(last_event.0, contract_id);
+    let topic0: Symbol = last_event.1.get(0).unwrap().into_val(&env);
+    let topic1: Address = last_event.1.get(1).unwrap().into_val(&env);
+    assert_eq!(topic0, Symbol::new(&env, "pause"));
+    assert_eq!(topic1, guardian);
+    let data: PauseState = last_event.2.into_val(&env);
+    assert_eq!(data, ops);
+
+    assert_eq!(
+        client.try_pause(
+            &guardian,
+            &PauseState {
+                withdraw: true,
+                ..PauseState::default()
+            }
+        ),
+        Err(Ok(VaultError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_unpause(&guardian, &ops),
+        Err(Ok(VaultError::Unauthorized))
+    );
+    assert!(client.get_pause_state().deduct);
+
+    // Clearing the guardian revokes its pause right.
+    client.set_guardian(&owner, &None);
+    assert_eq!(
+        client.try_pause(&guardian, &ops),
+        Err(Ok(VaultError::Unauthorized))
+    );
+}
+
+// ---------------------------------------------------------------------------
+// ownership transfer
+// ---------------------------------------------------------------------------
+
+/// propose_owner + accept_ownership hands the vault (and its withdraw rights and holds) over.
+#[test]
+fn ownership_transfer_two_step() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let new_owner = Address::generate(&env);
+    let deducter = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 300);
+    client.init(&owner, &usdc, &Some(300), &None, &None, &None);
+    client.add_deducter(&owner, &deducter);
+    let hold_id = Symbol::new(&env, "job");
+    client.hold(&deducter, &50, &hold_id, &100);
+
+    let pending = client.propose_owner(&new_owner, &None);
+    assert_eq!(pending.new_owner, new_owner);
+    assert_eq!(pending.expires_at, None);
+    assert_eq!(client.get_pending_owner(), Some(pending));
+    // Ownership does not move until accepted.
+    assert_eq!(client.get_meta().owner, owner);
+
+    let meta = client.accept_ownership();
+    let events = env.events().all();
+    let last_event = events.last().expect("expected ownership_transfer event");
+    let topic0: Symbol = last_event.1.get(0).unwrap().into_val(&env);
+    let topic1: Address = last_event.1.get(1).unwrap().into_val(&env);
+    let topic2: Address = last_event.1.get(2).unwrap().into_val(&env);
+    assert_eq!(topic0, Symbol::new(&env, "ownership_transfer"));
+    assert_eq!(topic1, owner);
+    assert_eq!(topic2, new_owner);
+    assert_eq!(meta.owner, new_owner);
+    assert_eq!(meta.balance, 300);
+    assert_eq!(client.get_pending_owner(), None);
+    assert_eq!(client.balance_of(&owner), 0);
+    assert_eq!(client.balance_of(&new_owner), 300);
+    assert_eq!(client.balance(), 250);
+
+    client.capture(&hold_id, &50);
+    assert_eq!(client.balance_of(&new_owner), 250);
+    client.withdraw(&100);
+    assert_eq!(usdc_client.balance(&new_owner), 100);
+}
+
+/// An expired proposal can no longer be accepted.
+#[test]
+fn accept_ownership_after_expiry_fails() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let new_owner = Address::generate(&env);
+    let (_, client) = create_vault(&env);
+    let usdc_token = Address::generate(&env);
+
+    env.mock_all_auths();
+    client.init(&owner, &usdc_token, &None, &None, &None, &None);
+
+    let pending = client.propose_owner(&new_owner, &Some(10));
+    assert_eq!(pending.expires_at, Some(env.ledger().sequence() + 10));
+
+    env.ledger().with_mut(|li| li.sequence_number += 11);
+    assert_eq!(
+        client.try_accept_ownership(),
+        Err(Ok(VaultError::TransferExpired))
+    );
+    assert_eq!(client.get_meta().owner, owner);
+}
+
+/// The owner can cancel a pending proposal; accepting afterwards fails.
+#[test]
+fn cancel_ownership_transfer_clears_pending() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let new_owner = Address::generate(&env);
+    let (_, client) = create_vault(&env);
+    let usdc_token = Address::generate(&env);
+
+    env.mock_all_auths();
+    client.init(&owner, &usdc_token, &None, &None, &None, &None);
+
+    assert_eq!(
+        client.try_cancel_ownership_transfer(),
+        Err(Ok(VaultError::NoPendingTransfer))
+    );
+    client.propose_owner(&new_owner, &Some(100));
+    client.cancel_ownership_transfer();
+    assert_eq!(client.get_pending_owner(), None);
+    assert_eq!(
+        client.try_accept_ownership(),
+        Err(Ok(VaultError::NoPendingTransfer))
+    );
+}
+
+/// Only the proposed owner can accept.
+#[test]
+fn accept_ownership_requires_new_owner_auth() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let new_owner = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let usdc_token = Address::generate(&env);
+
+    env.mock_all_auths();
+    client.init(&owner, &usdc_token, &None, &None, &None, &None);
+    client.propose_owner(&new_owner, &None);
+
+    // Only the current owner signs: accept_ownership must be rejected.
+    let result = client
+        .mock_auths(&[soroban_sdk::testutils::MockAuth {
+            address: &owner,
+            invoke: &soroban_sdk::testutils::MockAuthInvoke {
+                contract: &contract_id,
+                fn_name: "accept_ownership",
+                args: ().into_val(&env),
+                sub_invokes: &[],
+            },
+        }])
+        .try_accept_ownership();
+    assert!(result.is_err());
+    assert_eq!(client.get_meta().owner, owner);
+}
+
+// ---------------------------------------------------------------------------
+// admin management
+// ---------------------------------------------------------------------------
+
+/// get_admin returns the admin address set during init.
+#[test]
+fn get_admin_returns_correct_address() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let contract_id = env.register(CalloraVault {}, ());
+    let client = CalloraVaultClient::new(&env, &contract_id);
+    let usdc_token = Address::generate(&env);
+
+    env.mock_all_auths();
+    client.init(&owner, &usdc_token, &None, &None, &None, &None);
+
+    let admin = client.get_admin();
+    assert_eq!(admin, owner);
+}
+
+/// The admin only changes once the proposed admin accepts.
+#[test]
+fn propose_and_accept_admin() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let new_admin = Address::generate(&env);
+    let contract_id = env.register(CalloraVault {}, ());
+    let client = CalloraVaultClient::new(&env, &contract_id);
+    let usdc_token = Address::generate(&env);
+
+    env.mock_all_auths();
+    client.init(&owner, &usdc_token, &None, &None, &None, &None);
+
+    let pending = client.propose_admin(&owner, &new_admin, &100);
+    assert_eq!(pending.new_admin, new_admin);
+    assert_eq!(pending.expires_at, env.ledger().sequence() + 100);
+    assert_eq!(client.get_admin(), owner);
+    assert_eq!(client.get_pending_admin(), Some(pending));
+
+    assert_eq!(client.accept_admin(), new_admin);
+    let events = env.events().all();
+    let last_event = events.last().expect("expected admin_changed event");
+    let topic0: Symbol = last_event.1.get(0).unwrap().into_val(&env);
+    let topic1: Address = last_event.1.get(1).unwrap().into_val(&env);
+    let topic2: Address = last_event.1.get(2).unwrap().into_val(&env);
+    assert_eq!(topic0, Symbol::new(&env, "admin_changed"));
+    assert_eq!(topic1, owner);
+    assert_eq!(topic2, new_admin);
+
+    assert_eq!(client.get_admin(), new_admin);
+    assert_eq!(client.get_pending_admin(), None);
+}
+
+/// Non-admin callers cannot propose a new admin, and a zero deadline is rejected.
+#[test]
+fn propose_admin_validation() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let intruder = Address::generate(&env);
+    let new_admin = Address::generate(&env);
     let contract_id = env.register(CalloraVault {}, ());
     let client = CalloraVaultClient::new(&env, &contract_id);
     let usdc_token = Address::generate(&env);
 
     env.mock_all_auths();
-    client.init(&owner, &usdc_token, &Some(100), &None);
+    client.init(&owner, &usdc_token, &None, &None, &None, &None);
+
+    let result = client.try_propose_admin(&intruder, &new_admin, &100);
+    assert_eq!(result, Err(Ok(VaultError::Unauthorized)));
+    let result = client.try_propose_admin(&owner, &new_admin, &0);
+    assert_eq!(result, Err(Ok(VaultError::InvalidConfig)));
+    assert_eq!(client.get_pending_admin(), None);
+}
+
+/// A proposal that is not accepted before its deadline lapses.
+#[test]
+fn accept_admin_after_deadline_fails() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let new_admin = Address::generate(&env);
+    let (_, client) = create_vault(&env);
+    let usdc_token = Address::generate(&env);
+
+    env.mock_all_auths();
+    client.init(&owner, &usdc_token, &None, &None, &None, &None);
+    client.propose_admin(&owner, &new_admin, &10);
+
+    env.ledger().with_mut(|li| li.sequence_number += 11);
+    assert_eq!(
+        client.try_accept_admin(),
+        Err(Ok(VaultError::TransferExpired))
+    );
+    assert_eq!(client.get_admin(), owner);
+}
+
+/// The admin can cancel a pending handover.
+#[test]
+fn cancel_admin_transfer_clears_pending() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let new_admin = Address::generate(&env);
+    let (_, client) = create_vault(&env);
+    let usdc_token = Address::generate(&env);
+
+    env.mock_all_auths();
+    client.init(&owner, &usdc_token, &None, &None, &None, &None);
+
+    assert_eq!(
+        client.try_cancel_admin_transfer(&owner),
+        Err(Ok(VaultError::NoPendingTransfer))
+    );
+    client.propose_admin(&owner, &new_admin, &100);
+    assert_eq!(
+        client.try_cancel_admin_transfer(&new_admin),
+        Err(Ok(VaultError::Unauthorized))
+    );
+    client.cancel_admin_transfer(&owner);
+    assert_eq!(client.get_pending_admin(), None);
+    assert_eq!(
+        client.try_accept_admin(),
+        Err(Ok(VaultError::NoPendingTransfer))
+    );
+}
+
+// ---------------------------------------------------------------------------
+// distribute
+// ---------------------------------------------------------------------------
+
+/// Admin can distribute earned revenue from the vault to a developer.
+#[test]
+fn distribute_transfers_usdc_to_developer() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let developer = Address::generate(&env);
+    let (vault_address, client) = create_vault(&env);
+    let (usdc, _, usdc_admin_client) = create_usdc(&env, &admin);
+
+    env.mock_all_auths();
+
+    // Mint 1000 USDC into the vault and credit it as customer balance.
+    fund_vault(&usdc_admin_client, &vault_address, 1000);
+    client.init(&admin, &usdc, &Some(1000), &None, &None, &None);
+    client.add_deducter(&admin, &caller);
+
+    // Deduct 400 so it becomes earned revenue.
+    client.deduct(&caller, &400, &None);
+    assert_eq!(client.get_accrued_revenue(), 400);
+
+    // Distribute 300 to developer
+    client.distribute(&admin, &developer, &300);
+
+    // Verify developer received the funds
+    let usdc_client = token::Client::new(&env, &usdc);
+    assert_eq!(usdc_client.balance(&developer), 300);
+    assert_eq!(usdc_client.balance(&vault_address), 700);
+    assert_eq!(client.get_accrued_revenue(), 100);
+    assert_eq!(client.balance(), 600);
+}
+
+/// Non-admin cannot distribute funds.
+#[test]
+fn distribute_unauthorized_fails() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+    let intruder = Address::generate(&env);
+    let developer = Address::generate(&env);
+    let (vault_address, client) = create_vault(&env);
+    let (usdc, _, usdc_admin_client) = create_usdc(&env, &admin);
+
+    env.mock_all_auths();
+
+    client.init(&admin, &usdc, &Some(0), &None, &None, &None);
+    fund_vault(&usdc_admin_client, &vault_address, 1000);
+
+    let result = client.try_distribute(&intruder, &developer, &300);
+    assert_eq!(result, Err(Ok(VaultError::Unauthorized)));
+}
+
+/// Customer-prepaid balances cannot be distributed: only accrued revenue can.
+#[test]
+fn distribute_customer_balance_fails() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let developer = Address::generate(&env);
+    let (vault_address, client) = create_vault(&env);
+    let (usdc, usdc_client, usdc_admin_client) = create_usdc(&env, &admin);
+
+    env.mock_all_auths();
+
+    fund_vault(&usdc_admin_client, &vault_address, 500);
+    client.init(&admin, &usdc, &Some(500), &None, &None, &None);
+    client.add_deducter(&admin, &caller);
+    assert_eq!(client.get_accrued_revenue(), 0);
+
+    let result = client.try_distribute(&admin, &developer, &100);
+    assert_eq!(result, Err(Ok(VaultError::ExceedsAccruedRevenue)));
+
+    client.deduct(&caller, &50, &None);
+    let result = client.try_distribute(&admin, &developer, &51);
+    assert_eq!(result, Err(Ok(VaultError::ExceedsAccruedRevenue)));
+    client.distribute(&admin, &developer, &50);
+
+    // Invariant: token balance covers customer balance plus undistributed revenue.
+    assert_eq!(
+        usdc_client.balance(&vault_address),
+        client.balance() + client.get_accrued_revenue()
+    );
+}
+
+/// Revenue settled into a revenue pool is not accrued in the vault.
+#[test]
+fn deduct_with_revenue_pool_does_not_accrue() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (vault_address, client) = create_vault(&env);
+    let (pool_address, pool_client) = create_pool(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    pool_client.init(&owner, &usdc);
+    pool_client.add_vault(&owner, &vault_address);
+    fund_vault(&usdc_admin, &vault_address, 500);
+    client.init(&owner, &usdc, &Some(500), &None, &Some(pool_address), &None);
+    client.add_deducter(&owner, &caller);
+
+    client.deduct(&caller, &100, &None);
+    assert_eq!(client.get_accrued_revenue(), 0);
+}
+
+/// Distributing zero or negative amount fails.
+#[test]
+fn distribute_zero_amount_fails() {
+    let env = Env::default();
+    let admin = Address::generate(&env);
+    let developer = Address::generate(&env);
+    let (vault_address, client) = create_vault(&env);
+    let (usdc, _, usdc_admin_client) = create_usdc(&env, &admin);
+
+    env.mock_all_auths();
+
+    client.init(&admin, &usdc, &Some(0), &None, &None, &None);
+    fund_vault(&usdc_admin_client, &vault_address, 1000);
+
+    let result = client.try_distribute(&admin, &developer, &0);
+    assert_eq!(result, Err(Ok(VaultError::InvalidAmount)));
+}
+
+/// batch_distribute pays every developer from accrued revenue, or nobody.
+#[test]
+fn batch_distribute_all_or_nothing() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let dev_a = Address::generate(&env);
+    let dev_b = Address::generate(&env);
+    let (vault_address, client) = create_vault(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &vault_address, 1000);
+    client.init(&owner, &usdc, &Some(1000), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+    client.deduct(&caller, &300, &None);
+
+    let too_much = soroban_sdk::vec![&env, (dev_a.clone(), 200), (dev_b.clone(), 101)];
+    assert_eq!(
+        client.try_batch_distribute(&owner, &too_much),
+        Err(Ok(VaultError::ExceedsAccruedRevenue))
+    );
+    assert_eq!(
+        client.try_batch_distribute(&owner, &Vec::new(&env)),
+        Err(Ok(VaultError::EmptyBatch))
+    );
+    let mut oversized = Vec::new(&env);
+    for _ in 0..=MAX_DISTRIBUTE_BATCH {
+        oversized.push_back((dev_a.clone(), 1));
+    }
+    assert_eq!(
+        client.try_batch_distribute(&owner, &oversized),
+        Err(Ok(VaultError::BatchTooLarge))
+    );
+
+    let payouts = soroban_sdk::vec![&env, (dev_a.clone(), 200), (dev_b.clone(), 100)];
+    assert_eq!(client.batch_distribute(&owner, &payouts), 300);
+    let events = env.events().all();
+    let last_event = events.last().expect("expected batch_distribute event");
+    let topic0: Symbol = last_event.1.get(0).unwrap().into_val(&env);
+    let data: (u32, i128) = last_event.2.into_val(&env);
+    assert_eq!(topic0, Symbol::new(&env, "batch_distribute"));
+    assert_eq!(data, (2, 300));
+    assert_eq!(usdc_client.balance(&dev_a), 200);
+    assert_eq!(usdc_client.balance(&dev_b), 100);
+    assert_eq!(client.get_accrued_revenue(), 0);
+}
+
+// ---------------------------------------------------------------------------
+// refund
+// ---------------------------------------------------------------------------
+
+/// Without a pool, refunds come out of accrued revenue and go back to the charged sub-account.
+#[test]
+fn refund_credits_charged_sub_account_from_accrued() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let attacker = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    client.init(&owner, &usdc, &None, &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+    fund_depositor(&env, &usdc_admin, &usdc_client, &alice, &contract_id, 300);
+    client.deposit(&alice, &300);
+    let rid = Symbol::new(&env, "call1");
+    client.deduct_from(&caller, &alice, &100, &Some(rid.clone()));
+
+    let balance = client.refund(&caller, &rid, &40);
+    let events = env.events().all();
+    let last_event = events.last().expect("expected refund event");
+    let topic0: Symbol = last_event.1.get(0).unwrap().into_val(&env);
+    let topic2: Symbol = last_event.1.get(2).unwrap().into_val(&env);
+    let topic3: Address = last_event.1.get(3).unwrap().into_val(&env);
+    assert_eq!(topic0, Symbol::new(&env, "refund"));
+    assert_eq!(topic2, rid);
+    assert_eq!(topic3, alice);
+    assert_eq!(balance, 240);
+    assert_eq!(client.balance_of(&alice), 240);
+    assert_eq!(client.get_accrued_revenue(), 60);
+    assert_eq!(client.get_deduct_record(&rid).unwrap().refunded, 40);
+
+    // The admin may refund too, but never beyond the original charge.
+    assert_eq!(
+        client.try_refund(&owner, &rid, &61),
+        Err(Ok(VaultError::ExceedsDeducted))
+    );
+    client.refund(&owner, &rid, &60);
+    assert_eq!(client.balance_of(&alice), 300);
+    assert_eq!(client.get_accrued_revenue(), 0);
+
+    assert_eq!(
+        client.try_refund(&caller, &Symbol::new(&env, "nope"), &1),
+        Err(Ok(VaultError::RequestNotFound))
+    );
+    assert_eq!(
+        client.try_refund(&attacker, &rid, &1),
+        Err(Ok(VaultError::Unauthorized))
+    );
+}
+
+/// With a pool, the refunded USDC is pulled back from the pool into the vault.
+#[test]
+fn refund_pulls_funds_back_from_revenue_pool() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (pool_addr, pool_client) = create_pool(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    pool_client.init(&owner, &usdc);
+    pool_client.add_vault(&owner, &contract_id);
+    fund_vault(&usdc_admin, &contract_id, 500);
+    client.init(
+        &owner,
+        &usdc,
+        &Some(500),
+        &None,
+        &Some(pool_addr.clone()),
+        &None,
+    );
+    client.add_deducter(&owner, &caller);
+    let rid = Symbol::new(&env, "call1");
+    client.deduct(&caller, &150, &Some(rid.clone()));
+    assert_eq!(pool_client.get_refundable(&contract_id), 150);
+
+    let balance = client.refund(&caller, &rid, &150);
+    assert_eq!(balance, 500);
+    assert_eq!(usdc_client.balance(&pool_addr), 0);
+    assert_eq!(usdc_client.balance(&contract_id), 500);
+    assert_eq!(pool_client.get_refundable(&contract_id), 0);
+}
+
+// ---------------------------------------------------------------------------
+// disputes
+// ---------------------------------------------------------------------------
+
+/// A dispute freezes the charge in the pool until the admin resolves it with a partial refund.
+#[test]
+fn dispute_freezes_pool_funds_until_resolved() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let admin = Address::generate(&env);
+    let developer = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (pool_addr, pool_client) = create_pool(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    pool_client.init(&admin, &usdc);
+    pool_client.add_vault(&admin, &contract_id);
+    fund_vault(&usdc_admin, &contract_id, 500);
+    client.init(
+        &owner,
+        &usdc,
+        &Some(500),
+        &None,
+        &Some(pool_addr.clone()),
+        &None,
+    );
+    client.add_deducter(&owner, &caller);
+    let rid = Symbol::new(&env, "call1");
+    client.deduct(&caller, &200, &Some(rid.clone()));
+
+    let dispute = client.dispute(&rid, &7);
+    let events = env.events().all();
+    let last_event = events.last().expect("expected dispute_opened event");
+    let topic0: Symbol = last_event.1.get(0).unwrap().into_val(&env);
+    let data: (u32, i128) = last_event.2.into_val(&env);
+    assert_eq!(topic0, Symbol::new(&env, "dispute_opened"));
+    assert_eq!(data, (7, 200));
+    assert_eq!(dispute.amount, 200);
+    assert_eq!(client.get_dispute(&rid), Some(dispute));
+    assert_eq!(pool_client.get_frozen(&contract_id), 200);
+    assert_eq!(
+        pool_client.try_distribute(&admin, &developer, &1),
+        Err(Ok(callora_revenue_pool::PoolError::InsufficientUsdc))
+    );
+    assert_eq!(
+        client.try_refund(&caller, &rid, &10),
+        Err(Ok(VaultError::DisputeOpen))
+    );
+    assert_eq!(
+        client.try_dispute(&rid, &7),
+        Err(Ok(VaultError::DisputeOpen))
+    );
+
+    let balance = client.resolve_dispute(&owner, &rid, &2_500);
+    let events = env.events().all();
+    let last_event = events.last().expect("expected dispute_resolved event");
+    let topic0: Symbol = last_event.1.get(0).unwrap().into_val(&env);
+    let data: (i128, i128) = last_event.2.into_val(&env);
+    assert_eq!(topic0, Symbol::new(&env, "dispute_resolved"));
+    assert_eq!(data, (50, 150));
+    assert_eq!(balance, 350);
+    assert_eq!(client.get_dispute(&rid), None);
+    assert_eq!(client.get_deduct_record(&rid).unwrap().refunded, 50);
+    assert_eq!(pool_client.get_frozen(&contract_id), 0);
+    assert_eq!(usdc_client.balance(&pool_addr), 150);
+
+    pool_client.distribute(&admin, &developer, &150);
+}
+
+/// Without a pool, disputes freeze accrued revenue; the window and resolution rules apply.
+#[test]
+fn dispute_window_and_frozen_accrued_revenue() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let developer = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 500);
+    client.init(&owner, &usdc, &Some(500), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+    assert_eq!(client.get_dispute_window(), DEFAULT_DISPUTE_WINDOW);
+    assert_eq!(
+        client.try_set_dispute_window(&owner, &0),
+        Err(Ok(VaultError::InvalidConfig))
+    );
+    client.set_dispute_window(&owner, &10);
+
+    let late = Symbol::new(&env, "late");
+    client.deduct(&caller, &100, &Some(late.clone()));
+    env.ledger().with_mut(|li| li.sequence_number += 11);
+    assert_eq!(
+        client.try_dispute(&late, &1),
+        Err(Ok(VaultError::DisputeWindowClosed))
+    );
+
+    let rid = Symbol::new(&env, "call2");
+    client.deduct(&caller, &100, &Some(rid.clone()));
+    client.dispute(&rid, &1);
+    assert_eq!(
+        client.try_distribute(&owner, &developer, &101),
+        Err(Ok(VaultError::ExceedsAccruedRevenue))
+    );
+    assert_eq!(
+        client.try_resolve_dispute(&owner, &rid, &10_001),
+        Err(Ok(VaultError::InvalidConfig))
+    );
+
+    client.resolve_dispute(&owner, &rid, &10_000);
+    assert_eq!(client.balance(), 400);
+    assert_eq!(client.get_accrued_revenue(), 100);
+    assert_eq!(
+        client.try_resolve_dispute(&owner, &rid, &0),
+        Err(Ok(VaultError::DisputeNotFound))
+    );
+    client.distribute(&owner, &developer, &100);
+}
+
+// ---------------------------------------------------------------------------
+// batch_deduct
+// ---------------------------------------------------------------------------
+
+/// Batch deduct processes multiple items and emits multiple events.
+#[test]
+fn batch_deduct_multiple_items() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 1000);
+    client.init(&owner, &usdc, &Some(1000), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+
+    let items = soroban_sdk::vec![
+        &env,
+        DeductItem {
+            amount: 100,
+            request_id: Some(Symbol::new(&env, "req1")),
+            depositor: None,
+        },
+        DeductItem {
+            amount: 200,
+            request_id: None,
+            depositor: None,
+        },
+        DeductItem {
+            amount: 50,
+            request_id: Some(Symbol::new(&env, "req2")),
+            depositor: None,
+        }
+    ];
+
+    let remaining = client.batch_deduct(&caller, &items);
+    assert_eq!(remaining, 650); // 1000 - 100 - 200 - 50
+    assert_eq!(client.balance(), 650);
+}
+
+/// Batch deduct fails if any item would overdraw.
+#[test]
+fn batch_deduct_insufficient_balance_fails() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 100);
+    client.init(&owner, &usdc, &Some(100), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+
+    let items = soroban_sdk::vec![
+        &env,
+        DeductItem {
+            amount: 50,
+            request_id: None,
+            depositor: None,
+        },
+        DeductItem {
+            amount: 80, // would overdraw
+            request_id: None,
+            depositor: None,
+        }
+    ];
+
+    let result = client.try_batch_deduct(&caller, &items);
+    assert_eq!(result, Err(Ok(VaultError::InsufficientBalance)));
+    // Balance should remain unchanged after failed batch
+    assert_eq!(client.balance(), 100);
+}
+
+/// Empty batch fails.
+#[test]
+fn batch_deduct_empty_fails() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 100);
+    client.init(&owner, &usdc, &Some(100), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+
+    let items: soroban_sdk::Vec<DeductItem> = soroban_sdk::vec![&env];
+
+    let result = client.try_batch_deduct(&caller, &items);
+    assert_eq!(result, Err(Ok(VaultError::EmptyBatch)));
+}
+
+/// Batch deduct with zero amount fails.
+#[test]
+fn batch_deduct_zero_amount_fails() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 100);
+    client.init(&owner, &usdc, &Some(100), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
 
     let items = soroban_sdk::vec![
         &env,
         DeductItem {
-            amount: 50,
-            request_id: None
-        },
-        DeductItem {
-            amount: 80, // would overdraw
-            request_id: None
+            amount: 0,
+            request_id: None,
+            depositor: None,
         }
     ];
 
     let result = client.try_batch_deduct(&caller, &items);
-    assert!(result.is_err(), "expected error for batch overdraw");
-    // Balance should remain unchanged after failed batch
+    assert_eq!(result, Err(Ok(VaultError::InvalidAmount)));
+}
+
+// ---------------------------------------------------------------------------
+// holds
+// ---------------------------------------------------------------------------
+
+/// Held funds are excluded from balance() and cannot be deducted or withdrawn.
+#[test]
+fn hold_reserves_funds() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 100);
+    client.init(&owner, &usdc, &Some(100), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+
+    let hold_id = Symbol::new(&env, "h1");
+    let hold = client.hold(&caller, &70, &hold_id, &100);
+    let events = env.events().all();
+    let last_event = events.last().expect("expected hold event");
+    let topic0: Symbol = last_event.1.get(0).unwrap().into_val(&env);
+    assert_eq!(topic0, Symbol::new(&env, "hold"));
+    assert_eq!(hold.amount, 70);
+    assert_eq!(client.get_hold(&hold_id), Some(hold));
+    assert_eq!(client.balance(), 30);
+    assert_eq!(client.get_meta().balance, 100);
+
+    assert_eq!(
+        client.try_withdraw(&31),
+        Err(Ok(VaultError::InsufficientBalance))
+    );
+    assert_eq!(
+        client.try_deduct(&caller, &31, &None),
+        Err(Ok(VaultError::InsufficientBalance))
+    );
+    assert_eq!(
+        client.try_hold(&caller, &10, &hold_id, &100),
+        Err(Ok(VaultError::DuplicateHold))
+    );
+    assert_eq!(
+        client.try_hold(&caller, &10, &Symbol::new(&env, "h2"), &0),
+        Err(Ok(VaultError::InvalidConfig))
+    );
+
+    client.release(&hold_id);
+    assert_eq!(client.get_hold(&hold_id), None);
     assert_eq!(client.balance(), 100);
+    client.withdraw(&100);
 }
 
-/// Empty batch fails.
+/// capture settles the final amount through the revenue pool and frees the remainder.
 #[test]
-fn batch_deduct_empty_fails() {
+fn capture_settles_final_amount() {
     let env = Env::default();
     let owner = Address::generate(&env);
     let caller = Address::generate(&env);
-    let contract_id = env.register(CalloraVault {}, ());
-    let client = CalloraVaultClient::new(&env, &contract_id);
-    let usdc_token = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (pool_addr, pool_client) = create_pool(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
 
     env.mock_all_auths();
-    client.init(&owner, &usdc_token, &Some(100), &None);
+    pool_client.init(&owner, &usdc);
+    pool_client.add_vault(&owner, &contract_id);
+    fund_vault(&usdc_admin, &contract_id, 500);
+    client.init(
+        &owner,
+        &usdc,
+        &Some(500),
+        &None,
+        &Some(pool_addr.clone()),
+        &None,
+    );
+    client.add_deducter(&owner, &caller);
 
-    let items: soroban_sdk::Vec<DeductItem> = soroban_sdk::vec![&env];
+    let hold_id = Symbol::new(&env, "job");
+    client.hold(&caller, &200, &hold_id, &100);
+    assert_eq!(
+        client.try_capture(&hold_id, &201),
+        Err(Ok(VaultError::InvalidAmount))
+    );
 
-    let result = client.try_batch_deduct(&caller, &items);
-    assert!(result.is_err(), "expected error for empty batch");
+    let remaining = client.capture(&hold_id, &120);
+    assert_eq!(remaining, 380);
+    assert_eq!(client.balance(), 380);
+    assert_eq!(usdc_client.balance(&pool_addr), 120);
+    assert_eq!(
+        client.try_capture(&hold_id, &10),
+        Err(Ok(VaultError::HoldNotFound))
+    );
 }
 
-/// Batch deduct with zero amount fails.
+/// An expired hold stops counting against the balance and can no longer be captured.
 #[test]
-fn batch_deduct_zero_amount_fails() {
+fn hold_expires_automatically() {
     let env = Env::default();
     let owner = Address::generate(&env);
     let caller = Address::generate(&env);
-    let contract_id = env.register(CalloraVault {}, ());
-    let client = CalloraVaultClient::new(&env, &contract_id);
-    let usdc_token = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 100);
+    client.init(&owner, &usdc, &Some(100), &None, &None, &Some(80));
+    client.add_deducter(&owner, &caller);
+
+    assert_eq!(
+        client.try_hold(&caller, &90, &Symbol::new(&env, "big"), &50),
+        Err(Ok(VaultError::ExceedsMaxDeduct))
+    );
+    let hold_id = Symbol::new(&env, "h1");
+    client.hold(&caller, &80, &hold_id, &10);
+    assert_eq!(client.balance(), 20);
+
+    env.ledger().with_mut(|li| li.sequence_number += 11);
+    assert_eq!(client.balance(), 100);
+    assert_eq!(
+        client.try_capture(&hold_id, &80),
+        Err(Ok(VaultError::HoldExpired))
+    );
+
+    // Reusing the id expires the lapsed hold first.
+    client.hold(&caller, &50, &hold_id, &100);
+    assert_eq!(client.get_hold(&hold_id).unwrap().amount, 50);
+    assert_eq!(client.balance(), 50);
+}
+
+/// A lapsed hold stops counting at expiry even while a later one is open; expire_hold only
+/// clears its record, and open holds per sub-account are capped.
+#[test]
+fn lapsed_hold_released_while_others_open() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 500);
+    client.init(&owner, &usdc, &Some(500), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+
+    let short = Symbol::new(&env, "short");
+    let long = Symbol::new(&env, "long");
+    client.hold(&caller, &300, &short, &10);
+    client.hold(&caller, &10, &long, &1_000);
+    assert_eq!(
+        client.try_expire_hold(&short),
+        Err(Ok(VaultError::HoldActive))
+    );
+
+    env.ledger().with_mut(|li| li.sequence_number += 20);
+    assert_eq!(client.balance(), 490);
+    client.withdraw(&200);
+    client.expire_hold(&short);
+    let events = env.events().all();
+    let last_event = events.last().expect("expected hold_expired event");
+    let topic0: Symbol = last_event.1.get(0).unwrap().into_val(&env);
+    assert_eq!(topic0, Symbol::new(&env, "hold_expired"));
+    assert_eq!(client.get_hold(&short), None);
+    assert_eq!(client.balance(), 290);
+
+    for i in 1..MAX_OPEN_HOLDS {
+        client.hold(
+            &caller,
+            &1,
+            &Symbol::new(&env, &std::format!("h{i}")),
+            &1_000,
+        );
+    }
+    assert_eq!(
+        client.try_hold(&caller, &1, &Symbol::new(&env, "extra"), &1_000),
+        Err(Ok(VaultError::TooManyHolds))
+    );
+    client.release(&long);
+    client.hold(&caller, &1, &Symbol::new(&env, "extra"), &1_000);
+}
+
+/// A deducter removed from the allowlist can no longer capture its outstanding holds.
+#[test]
+fn capture_requires_current_deducter() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 500);
+    client.init(&owner, &usdc, &Some(500), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+    let hold_id = Symbol::new(&env, "job");
+    client.hold(&caller, &200, &hold_id, &100);
+
+    client.remove_deducter(&owner, &caller);
+    assert_eq!(
+        client.try_capture(&hold_id, &200),
+        Err(Ok(VaultError::Unauthorized))
+    );
+    assert_eq!(client.get_meta().balance, 500);
+    client.release(&hold_id);
+    assert_eq!(client.balance(), 500);
+}
+
+// ---------------------------------------------------------------------------
+// spending cap
+// ---------------------------------------------------------------------------
+
+/// Deducts count against the window's budget, refusals fail with BudgetExceeded without
+/// charging anything, and the budget resets when the next window starts.
+#[test]
+fn spend_cap_limits_deducts_per_window() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
 
     env.mock_all_auths();
-    client.init(&owner, &usdc_token, &Some(100), &None);
+    env.ledger().with_mut(|li| li.timestamp = 86_400 * 10 + 500);
+    fund_vault(&usdc_admin, &contract_id, 1_000);
+    client.init(&owner, &usdc, &Some(1_000), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+    assert_eq!(client.get_spend_window(), None);
+
+    client.set_spend_cap(&Some(SpendCap {
+        period: SpendPeriod::Day,
+        limit: 100,
+    }));
+    client.deduct(&caller, &60, &None);
+    let window = client.get_spend_window().unwrap();
+    assert_eq!(window.window_start, 86_400 * 10);
+    assert_eq!(window.spent, 60);
+    assert_eq!(window.remaining, 40);
+
+    assert_eq!(
+        client.try_deduct(&caller, &50, &None),
+        Err(Ok(VaultError::BudgetExceeded))
+    );
+    assert_eq!(client.get_spend_window().unwrap().remaining, 40);
+    assert_eq!(client.balance(), 940);
 
     let items = soroban_sdk::vec![
         &env,
         DeductItem {
-            amount: 0,
-            request_id: None
-        }
+            amount: 20,
+            request_id: None,
+            depositor: None,
+        },
+        DeductItem {
+            amount: 20,
+            request_id: None,
+            depositor: None,
+        },
     ];
+    client.batch_deduct(&caller, &items);
+    assert_eq!(client.get_spend_window().unwrap().remaining, 0);
 
-    let result = client.try_batch_deduct(&caller, &items);
-    assert!(result.is_err(), "expected error for zero amount");
+    env.ledger().with_mut(|li| li.timestamp += 86_400);
+    assert_eq!(client.get_spend_window().unwrap().spent, 0);
+    client.deduct(&caller, &100, &None);
+    assert_eq!(client.balance(), 800);
+}
+
+/// Windows are fixed: spending resets at the boundary, so a full limit can be charged
+/// on each side of it.
+#[test]
+fn spend_cap_resets_at_fixed_window_boundary() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 86_400 * 11 - 1);
+    fund_vault(&usdc_admin, &contract_id, 1_000);
+    client.init(&owner, &usdc, &Some(1_000), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+    client.set_spend_cap(&Some(SpendCap {
+        period: SpendPeriod::Day,
+        limit: 100,
+    }));
+
+    client.deduct(&caller, &100, &None);
+    assert_eq!(client.get_spend_window().unwrap().window_start, 86_400 * 10);
+    assert_eq!(
+        client.try_deduct(&caller, &1, &None),
+        Err(Ok(VaultError::BudgetExceeded))
+    );
+
+    env.ledger().with_mut(|li| li.timestamp += 1);
+    let window = client.get_spend_window().unwrap();
+    assert_eq!(window.window_start, 86_400 * 11);
+    assert_eq!(window.remaining, 100);
+    client.deduct(&caller, &100, &None);
+    assert_eq!(client.balance(), 800);
+}
+
+/// Captures are budgeted too; the cap can be cleared and must have a positive limit.
+#[test]
+fn spend_cap_applies_to_capture() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 500);
+    client.init(&owner, &usdc, &Some(500), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+
+    assert_eq!(
+        client.try_set_spend_cap(&Some(SpendCap {
+            period: SpendPeriod::Days30,
+            limit: 0,
+        })),
+        Err(Ok(VaultError::InvalidConfig))
+    );
+    client.set_spend_cap(&Some(SpendCap {
+        period: SpendPeriod::Days30,
+        limit: 50,
+    }));
+
+    let hold_id = Symbol::new(&env, "job");
+    client.hold(&caller, &80, &hold_id, &100);
+    assert_eq!(
+        client.try_capture(&hold_id, &80),
+        Err(Ok(VaultError::BudgetExceeded))
+    );
+    client.capture(&hold_id, &50);
+
+    client.set_spend_cap(&None);
+    assert_eq!(client.get_spend_cap(), None);
+    client.deduct(&caller, &100, &None);
+    assert_eq!(client.balance(), 350);
 }
 
 // ---------------------------------------------------------------------------
@@ -590,16 +2450,17 @@ fn batch_deduct_zero_amount_fails() {
 fn withdraw_reduces_balance() {
     let env = Env::default();
     let owner = Address::generate(&env);
-    let contract_id = env.register(CalloraVault {}, ());
-    let client = CalloraVaultClient::new(&env, &contract_id);
-    let usdc_token = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
 
     env.mock_all_auths();
-    client.init(&owner, &usdc_token, &Some(500), &None);
+    fund_vault(&usdc_admin, &contract_id, 500);
+    client.init(&owner, &usdc, &Some(500), &None, &None, &None);
 
     let remaining = client.withdraw(&200);
     assert_eq!(remaining, 300);
     assert_eq!(client.balance(), 300);
+    assert_eq!(usdc_client.balance(&owner), 200);
 }
 
 /// Withdrawing more than balance fails.
@@ -607,15 +2468,15 @@ fn withdraw_reduces_balance() {
 fn withdraw_insufficient_balance_fails() {
     let env = Env::default();
     let owner = Address::generate(&env);
-    let contract_id = env.register(CalloraVault {}, ());
-    let client = CalloraVaultClient::new(&env, &contract_id);
-    let usdc_token = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
 
     env.mock_all_auths();
-    client.init(&owner, &usdc_token, &Some(100), &None);
+    fund_vault(&usdc_admin, &contract_id, 100);
+    client.init(&owner, &usdc, &Some(100), &None, &None, &None);
 
     let result = client.try_withdraw(&500);
-    assert!(result.is_err(), "expected error for insufficient balance");
+    assert_eq!(result, Err(Ok(VaultError::InsufficientBalance)));
 }
 
 /// Withdrawing zero or negative fails.
@@ -623,15 +2484,15 @@ fn withdraw_insufficient_balance_fails() {
 fn withdraw_zero_fails() {
     let env = Env::default();
     let owner = Address::generate(&env);
-    let contract_id = env.register(CalloraVault {}, ());
-    let client = CalloraVaultClient::new(&env, &contract_id);
-    let usdc_token = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
 
     env.mock_all_auths();
-    client.init(&owner, &usdc_token, &Some(100), &None);
+    fund_vault(&usdc_admin, &contract_id, 100);
+    client.init(&owner, &usdc, &Some(100), &None, &None, &None);
 
     let result = client.try_withdraw(&0);
-    assert!(result.is_err(), "expected error for zero amount");
+    assert_eq!(result, Err(Ok(VaultError::InvalidAmount)));
 }
 
 // ---------------------------------------------------------------------------
@@ -644,16 +2505,17 @@ fn withdraw_to_reduces_balance() {
     let env = Env::default();
     let owner = Address::generate(&env);
     let recipient = Address::generate(&env);
-    let contract_id = env.register(CalloraVault {}, ());
-    let client = CalloraVaultClient::new(&env, &contract_id);
-    let usdc_token = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
 
     env.mock_all_auths();
-    client.init(&owner, &usdc_token, &Some(500), &None);
+    fund_vault(&usdc_admin, &contract_id, 500);
+    client.init(&owner, &usdc, &Some(500), &None, &None, &None);
 
     let remaining = client.withdraw_to(&recipient, &150);
     assert_eq!(remaining, 350);
     assert_eq!(client.balance(), 350);
+    assert_eq!(usdc_client.balance(&recipient), 150);
 }
 
 /// Withdrawing to address with insufficient balance fails.
@@ -662,15 +2524,15 @@ fn withdraw_to_insufficient_balance_fails() {
     let env = Env::default();
     let owner = Address::generate(&env);
     let recipient = Address::generate(&env);
-    let contract_id = env.register(CalloraVault {}, ());
-    let client = CalloraVaultClient::new(&env, &contract_id);
-    let usdc_token = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
 
     env.mock_all_auths();
-    client.init(&owner, &usdc_token, &Some(100), &None);
+    fund_vault(&usdc_admin, &contract_id, 100);
+    client.init(&owner, &usdc, &Some(100), &None, &None, &None);
 
     let result = client.try_withdraw_to(&recipient, &500);
-    assert!(result.is_err(), "expected error for insufficient balance");
+    assert_eq!(result, Err(Ok(VaultError::InsufficientBalance)));
 }
 
 // ---------------------------------------------------------------------------
@@ -682,15 +2544,16 @@ fn withdraw_to_insufficient_balance_fails() {
 fn deposit_below_minimum_fails() {
     let env = Env::default();
     let owner = Address::generate(&env);
-    let contract_id = env.register(CalloraVault {}, ());
-    let client = CalloraVaultClient::new(&env, &contract_id);
-    let usdc_token = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
 
     env.mock_all_auths();
-    client.init(&owner, &usdc_token, &Some(100), &Some(50)); // min_deposit = 50
+    fund_vault(&usdc_admin, &contract_id, 100);
+    client.init(&owner, &usdc, &Some(100), &Some(50), &None, &None); // min_deposit = 50
+    fund_depositor(&env, &usdc_admin, &usdc_client, &owner, &contract_id, 30);
 
-    let result = client.try_deposit(&30); // below minimum
-    assert!(result.is_err(), "expected error for deposit below minimum");
+    let result = client.try_deposit(&owner, &30); // below minimum
+    assert_eq!(result, Err(Ok(VaultError::BelowMinDeposit)));
 }
 
 /// Deposits at or above min_deposit succeed.
@@ -698,17 +2561,305 @@ fn deposit_below_minimum_fails() {
 fn deposit_at_minimum_succeeds() {
     let env = Env::default();
     let owner = Address::generate(&env);
-    let contract_id = env.register(CalloraVault {}, ());
-    let client = CalloraVaultClient::new(&env, &contract_id);
-    let usdc_token = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
 
     env.mock_all_auths();
-    client.init(&owner, &usdc_token, &Some(100), &Some(50)); // min_deposit = 50
+    fund_vault(&usdc_admin, &contract_id, 100);
+    client.init(&owner, &usdc, &Some(100), &Some(50), &None, &None); // min_deposit = 50
+    fund_depositor(&env, &usdc_admin, &usdc_client, &owner, &contract_id, 50);
 
-    let new_balance = client.deposit(&50);
+    let new_balance = client.deposit(&owner, &50);
     assert_eq!(new_balance, 150);
 }
 
+// ---------------------------------------------------------------------------
+// config setters
+// ---------------------------------------------------------------------------
+
+/// The admin can retune min_deposit and max_deduct; get_config reflects the change.
+#[test]
+fn set_min_deposit_and_max_deduct() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 500);
+    client.init(&owner, &usdc, &Some(500), &Some(10), &None, &Some(100));
+    client.add_deducter(&owner, &caller);
+
+    client.set_max_deduct(&owner, &200);
+    let events = env.events().all();
+    let last_event = events.last().expect("expected config_updated event");
+    let topic0: Symbol = last_event.1.get(0).unwrap().into_val(&env);
+    let topic1: Symbol = last_event.1.get(1).unwrap().into_val(&env);
+    let data: (i128, i128) = last_event.2.into_val(&env);
+    assert_eq!(topic0, Symbol::new(&env, "config_updated"));
+    assert_eq!(topic1, Symbol::new(&env, "max_deduct"));
+    assert_eq!(data, (100, 200));
+
+    client.set_min_deposit(&owner, &25);
+    let config = client.get_config();
+    assert_eq!(config.owner, owner);
+    assert_eq!(config.admin, owner);
+    assert_eq!(config.usdc_token, usdc);
+    assert_eq!(config.min_deposit, 25);
+    assert_eq!(config.max_deduct, 200);
+    assert_eq!(config.request_ttl, DEFAULT_REQUEST_TTL);
+    assert_eq!(config.ttl_config, client.get_ttl_config());
+    assert_eq!(config.dispute_window, DEFAULT_DISPUTE_WINDOW);
+
+    client.deduct(&caller, &150, &None);
+    fund_depositor(&env, &usdc_admin, &usdc_client, &owner, &contract_id, 20);
+    assert_eq!(
+        client.try_deposit(&owner, &20),
+        Err(Ok(VaultError::BelowMinDeposit))
+    );
+}
+
+/// Setters are admin-only and keep min_deposit <= max_deduct.
+#[test]
+fn config_setters_validation() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let attacker = Address::generate(&env);
+    let (_, client) = create_vault(&env);
+    let usdc_token = Address::generate(&env);
+
+    env.mock_all_auths();
+    client.init(&owner, &usdc_token, &None, &Some(50), &None, &Some(100));
+
+    assert_eq!(
+        client.try_set_max_deduct(&attacker, &500),
+        Err(Ok(VaultError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_set_max_deduct(&owner, &40),
+        Err(Ok(VaultError::InvalidConfig))
+    );
+    assert_eq!(
+        client.try_set_min_deposit(&owner, &101),
+        Err(Ok(VaultError::InvalidConfig))
+    );
+    assert_eq!(
+        client.try_set_min_deposit(&owner, &-1),
+        Err(Ok(VaultError::InvalidConfig))
+    );
+    assert_eq!(client.get_meta().min_deposit, 50);
+    assert_eq!(client.get_max_deduct(), 100);
+}
+
+// ---------------------------------------------------------------------------
+// upgrade / migrate
+// ---------------------------------------------------------------------------
+
+/// A vault written by the v1 layout gets its balance moved into the owner's sub-account,
+/// its deduct settings seeded, and its surplus USDC recorded as accrued revenue.
+#[test]
+fn migrate_from_v1_seeds_owner_sub_account() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 300);
+    client.init(&owner, &usdc, &Some(300), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+    assert_eq!(client.get_schema_version(), SCHEMA_VERSION);
+
+    // Rewind storage to what a pre-versioning vault would have written: v1 had no
+    // sub-accounts, deduct cap, pool setting or accrued-revenue tracking, and 50 USDC of
+    // earlier deducts is still sitting in the contract.
+    fund_vault(&usdc_admin, &contract_id, 50);
+    env.as_contract(&contract_id, || {
+        let inst = env.storage().instance();
+        inst.remove(&Symbol::new(&env, SCHEMA_VERSION_KEY));
+        inst.remove(&Symbol::new(&env, MAX_DEDUCT_KEY));
+        inst.remove(&Symbol::new(&env, REVENUE_POOL_KEY));
+        inst.remove(&Symbol::new(&env, ACCRUED_REVENUE_KEY));
+        env.storage()
+            .persistent()
+            .remove(&(Symbol::new(&env, DEPOSITOR_KEY), owner.clone()));
+    });
+    assert_eq!(client.get_schema_version(), 1);
+    assert_eq!(client.balance_of(&owner), 0);
+    assert_eq!(
+        client.try_deduct(&caller, &10, &None),
+        Err(Ok(VaultError::MigrationPending))
+    );
+
+    assert_eq!(client.migrate(&owner), SCHEMA_VERSION);
+    let events = env.events().all();
+    let last_event = events.last().expect("expected migrated event");
+    let topic0: Symbol = last_event.1.get(0).unwrap().into_val(&env);
+    let data: (u32, u32) = last_event.2.into_val(&env);
+    assert_eq!(topic0, Symbol::new(&env, "migrated"));
+    assert_eq!(data, (1, SCHEMA_VERSION));
+    assert_eq!(client.balance_of(&owner), 300);
+    assert_eq!(client.get_max_deduct(), DEFAULT_MAX_DEDUCT);
+    assert_eq!(client.get_revenue_pool(), None);
+    assert_eq!(client.get_accrued_revenue(), 50);
+
+    // Running it again is a no-op.
+    assert_eq!(client.migrate(&owner), SCHEMA_VERSION);
+    assert_eq!(client.balance_of(&owner), 300);
+    client.deduct(&caller, &100, &None);
+    assert_eq!(client.get_accrued_revenue(), 150);
+    client.withdraw(&200);
+    assert_eq!(usdc_client.balance(&owner), 200);
+}
+
+/// Between `upgrade` and `migrate` no balance may move: a deposit credited to the owner's
+/// sub-account would otherwise be counted again when `migrate` seeds it from `VaultMeta.balance`.
+#[test]
+fn balance_changes_blocked_until_migrated() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let caller = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, usdc_client, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 300);
+    client.init(&owner, &usdc, &Some(300), &None, &None, &None);
+    client.add_deducter(&owner, &caller);
+    fund_depositor(&env, &usdc_admin, &usdc_client, &owner, &contract_id, 100);
+    env.as_contract(&contract_id, || {
+        env.storage()
+            .instance()
+            .remove(&Symbol::new(&env, SCHEMA_VERSION_KEY));
+        env.storage()
+            .persistent()
+            .remove(&(Symbol::new(&env, DEPOSITOR_KEY), owner.clone()));
+    });
+
+    assert_eq!(
+        client.try_deposit(&owner, &100),
+        Err(Ok(VaultError::MigrationPending))
+    );
+    assert_eq!(
+        client.try_withdraw(&10),
+        Err(Ok(VaultError::MigrationPending))
+    );
+    assert_eq!(
+        client.try_hold(&caller, &10, &Symbol::new(&env, "h1"), &100),
+        Err(Ok(VaultError::MigrationPending))
+    );
+    assert_eq!(client.balance(), 300);
+
+    client.migrate(&owner);
+    client.deposit(&owner, &100);
+    assert_eq!(client.balance_of(&owner), 400);
+    assert_eq!(client.balance(), 400);
+    assert_eq!(usdc_client.balance(&contract_id), 400);
+    client.withdraw(&400);
+    assert_eq!(usdc_client.balance(&owner), 400);
+}
+
+/// upgrade and migrate are admin-only.
+#[test]
+fn upgrade_and_migrate_require_admin() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let attacker = Address::generate(&env);
+    let (_, client) = create_vault(&env);
+    let usdc_token = Address::generate(&env);
+
+    env.mock_all_auths();
+    client.init(&owner, &usdc_token, &None, &None, &None, &None);
+
+    let hash = BytesN::from_array(&env, &[7; 32]);
+    assert_eq!(
+        client.try_upgrade(&attacker, &hash),
+        Err(Ok(VaultError::Unauthorized))
+    );
+    assert_eq!(
+        client.try_migrate(&attacker),
+        Err(Ok(VaultError::Unauthorized))
+    );
+}
+
+// ---------------------------------------------------------------------------
+// storage TTL
+// ---------------------------------------------------------------------------
+
+/// State changes extend instance and sub-account TTLs; anyone can extend them later.
+#[test]
+fn ttl_extended_on_state_change_and_on_demand() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let (usdc, _, usdc_admin) = create_usdc(&env, &owner);
+
+    env.mock_all_auths();
+    fund_vault(&usdc_admin, &contract_id, 100);
+    client.init(&owner, &usdc, &Some(100), &None, &None, &None);
+
+    let sub_key = (Symbol::new(&env, DEPOSITOR_KEY), owner.clone());
+    let ttls = || {
+        env.as_contract(&contract_id, || {
+            (
+                env.storage().instance().get_ttl(),
+                env.storage().persistent().get_ttl(&sub_key),
+            )
+        })
+    };
+    assert_eq!(ttls(), (DEFAULT_TTL_EXTEND_TO, DEFAULT_TTL_EXTEND_TO));
+
+    env.ledger().with_mut(|li| li.sequence_number += 600_000);
+    let aged = DEFAULT_TTL_EXTEND_TO - 600_000;
+    assert_eq!(ttls(), (aged, aged));
+
+    client.extend_ttl();
+    assert_eq!(ttls(), (DEFAULT_TTL_EXTEND_TO, aged));
+    client.extend_balance_ttl(&owner);
+    assert_eq!(ttls(), (DEFAULT_TTL_EXTEND_TO, DEFAULT_TTL_EXTEND_TO));
+}
+
+/// The admin can tune the TTL policy within the network's limits.
+#[test]
+fn set_ttl_config_validation() {
+    let env = Env::default();
+    let owner = Address::generate(&env);
+    let (contract_id, client) = create_vault(&env);
+    let usdc_token = Address::generate(&env);
+
+    env.mock_all_auths();
+    client.init(&owner, &usdc_token, &None, &None, &None, &None);
+    assert_eq!(
+        client.get_ttl_config(),
+        TtlConfig {
+            threshold: DEFAULT_TTL_THRESHOLD,
+            extend_to: DEFAULT_TTL_EXTEND_TO,
+        }
+    );
+
+    for bad in [(0, 100), (100, 100), (100, u32::MAX)] {
+        let config = TtlConfig {
+            threshold: bad.0,
+            extend_to: bad.1,
+        };
+        assert_eq!(
+            client.try_set_ttl_config(&owner, &config),
+            Err(Ok(VaultError::InvalidConfig))
+        );
+    }
+
+    let config = TtlConfig {
+        threshold: 1_500_000,
+        extend_to: 2_000_000,
+    };
+    client.set_ttl_config(&owner, &config);
+    assert_eq!(client.get_ttl_config(), config);
+    let ttl = env.as_contract(&contract_id, || env.storage().instance().get_ttl());
+    assert_eq!(ttl, 2_000_000);
+}
+
 // ---------------------------------------------------------------------------
 // double init guard
 // ---------------------------------------------------------------------------
@@ -723,8 +2874,8 @@ fn double_init_fails() {
     let usdc_token = Address::generate(&env);
 
     env.mock_all_auths();
-    client.init(&owner, &usdc_token, &Some(100), &None);
+    client.init(&owner, &usdc_token, &None, &None, &None, &None);
 
-    let result = client.try_init(&owner, &usdc_token, &Some(200), &None);
-    assert!(result.is_err(), "expected error for double init");
+    let result = client.try_init(&owner, &usdc_token, &None, &None, &None, &None);
+    assert_eq!(result, Err(Ok(VaultError::AlreadyInitialized)));
 }